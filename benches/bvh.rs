@@ -0,0 +1,80 @@
+// Compares closest-hit queries against a flat HittableList and a SAH BVH as the
+// object count grows. Run with `cargo bench --bench bvh`.
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use ray_tracer::ray_tracer::bvh::BvhNode;
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::hittable_list::HittableList;
+use ray_tracer::ray_tracer::interval::Interval;
+use ray_tracer::ray_tracer::material::Lambertian;
+use ray_tracer::ray_tracer::ray::Ray;
+use ray_tracer::ray_tracer::sampler::{IndependentSampler, Sampler};
+use ray_tracer::ray_tracer::sphere::Sphere;
+use ray_tracer::ray_tracer::vec3::{Color, Point3, Vec3};
+
+const RAY_COUNT: usize = 20_000;
+
+fn random_world(count: usize, rng: &mut IndependentSampler) -> HittableList {
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    let mut world = HittableList::new();
+    for _ in 0..count {
+        let center = Point3::new(
+            rng.next_1d() * 100.0 - 50.0,
+            rng.next_1d() * 100.0 - 50.0,
+            rng.next_1d() * 100.0 - 50.0,
+        );
+        world.add(Arc::new(Sphere::new(center, 0.2 + rng.next_1d() * 0.5, material.clone())));
+    }
+    world
+}
+
+fn random_rays(rng: &mut IndependentSampler) -> Vec<Ray> {
+    (0..RAY_COUNT)
+        .map(|_| Ray::new(Point3::new(0.0, 0.0, 0.0), rng.random_unit_vector()))
+        .collect()
+}
+
+// Average nanoseconds per closest-hit query
+fn time_queries(world: &dyn Hittable, rays: &[Ray]) -> f64 {
+    let start = Instant::now();
+    let mut hits = 0usize;
+    for ray in rays {
+        let mut rec = HitRecord::new();
+        if world.hit(ray, Interval::new(0.001, f64::INFINITY), &mut rec) {
+            hits += 1;
+        }
+    }
+    black_box(hits);
+    start.elapsed().as_nanos() as f64 / rays.len() as f64
+}
+
+fn main() {
+    let mut rng = IndependentSampler::new(0x5eed);
+    let rays = random_rays(&mut rng);
+
+    println!("{:>8} {:>14} {:>14} {:>10}", "objects", "list ns/ray", "bvh ns/ray", "speedup");
+    for exponent in 4..=13 {
+        let count = 1usize << exponent;
+        let world = random_world(count, &mut rng);
+
+        // Both structures must agree on the closest hit before timing means anything
+        let probe = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.3, -0.2));
+        let mut list_rec = HitRecord::new();
+        let list_hit = world.hit(&probe, Interval::new(0.001, f64::INFINITY), &mut list_rec);
+
+        let list_ns = time_queries(&world, &rays);
+        let bvh = BvhNode::new(world);
+
+        let mut bvh_rec = HitRecord::new();
+        let bvh_hit = bvh.hit(&probe, Interval::new(0.001, f64::INFINITY), &mut bvh_rec);
+        assert_eq!(list_hit, bvh_hit);
+        assert!(!list_hit || (list_rec.t - bvh_rec.t).abs() < 1e-9);
+
+        let bvh_ns = time_queries(&bvh, &rays);
+        println!("{:>8} {:>14.1} {:>14.1} {:>9.1}x", count, list_ns, bvh_ns, list_ns / bvh_ns);
+    }
+}
@@ -1,5 +1,6 @@
 use winit::window::Window;
 use crate::ray_tracer::camera::Camera;
+use crate::ray_tracer::renderer::Renderer;
 use winit::keyboard::KeyCode;
 use std::collections::HashSet;
 
@@ -17,7 +18,7 @@ pub struct State<'a> {
 }
 
 impl<'a> State<'a> {
-    pub async fn new(window: &'a Window, raytracer: &Camera) -> State<'a> {
+    pub async fn new(window: &'a Window, raytracer: &Renderer) -> State<'a> {
         let size = window.inner_size();
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             #[cfg(not(target_arch = "wasm32"))]
@@ -30,8 +31,7 @@ impl<'a> State<'a> {
         let adapter = instance
             .enumerate_adapters(wgpu::Backends::all())
             .into_iter()
-            .filter(|a| a.is_surface_supported(&surface))
-            .next()
+            .find(|a| a.is_surface_supported(&surface))
             .expect("No suitable GPU adapters found");
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
@@ -186,7 +186,7 @@ impl<'a> State<'a> {
     }
 
     pub fn window(&self) -> &Window {
-        &self.window
+        self.window
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
@@ -253,8 +253,8 @@ impl<'a> State<'a> {
         // This code gets ran every frame
     }
 
-    pub fn update_image(&mut self, raytracer: &crate::ray_tracer::camera::Camera) {
-        let pixels = raytracer.render_rgba();
+    pub fn update_image(&mut self, raytracer: &Renderer, show_heatmap: bool) {
+        let pixels = if show_heatmap { raytracer.render_heatmap() } else { raytracer.render_rgba() };
         let width = raytracer.image_width();
         let height = raytracer.image_height();
         self.queue.write_texture(
@@ -267,8 +267,8 @@ impl<'a> State<'a> {
             &pixels,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some((width * 4).into()),
-                rows_per_image: Some(height.into()),
+                bytes_per_row: Some(width * 4),
+                rows_per_image: Some(height),
             },
             wgpu::Extent3d {
                 width,
@@ -0,0 +1,343 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::ray_tracer::adaptive::AdaptiveSettings;
+use crate::ray_tracer::aperture::Aperture;
+use crate::ray_tracer::camera::Camera;
+use crate::ray_tracer::denoiser::DenoiserKind;
+use crate::ray_tracer::integrator::{PathIntegrator, PathSettings};
+use crate::ray_tracer::renderer::{self, Renderer};
+use crate::ray_tracer::image_writer;
+use crate::ray_tracer::sampler::SamplerKind;
+use crate::ray_tracer::scene::Scene;
+use crate::ray_tracer::tile::{TileProgress, TileSettings};
+use crate::ray_tracer::tonemap::ToneMapping;
+
+pub const USAGE: &str = "\
+Usage: ray_tracer [--scene <file.toml>] [--output <file.png|file.ppm|file.exr|file.pfm> [options]]
+
+Without --output the interactive window is opened.
+
+Options:
+  -S, --scene <path>      Load the world and camera from a TOML scene file
+
+Offline options:
+  -o, --output <path>     Render without a window and write the image to <path>.
+                          .exr and .pfm store linear radiance without tone mapping
+      --aovs              With .exr or .pfm output, also write depth, normal, albedo,
+                          sample count and variance passes
+  -s, --samples <n>       Samples per pixel, the most with --adaptive (default 64)
+      --adaptive <e>      Stop sampling pixels whose relative error is below <e>, e.g. 0.02
+      --min-samples <n>   Samples per pixel before --adaptive may stop (default 16)
+      --heatmap <path>    Also write a PNG showing how many samples each pixel took
+  -w, --width <n>         Image width in pixels, height follows 16:9 (default 650)
+  -d, --max-depth <n>     Maximum bounces per path (default 10)
+      --max-diffuse <n>   Maximum diffuse bounces (default: the maximum depth)
+      --max-specular <n>  Maximum mirror and glossy bounces (default: the maximum depth)
+      --max-transmission <n>
+                          Maximum refractions (default: the maximum depth)
+      --rr-depth <n>      Bounces before Russian roulette may end a path (default 3)
+      --no-rr             Never end paths early through Russian roulette
+      --denoise <name>    Denoise the result with the bilateral or atrous filter
+      --aperture <r>      Lens radius for depth of field, 0 for a pinhole (default: the scene's)
+      --focus-distance <d>
+                          Distance along the view direction that is in focus
+      --autofocus         Focus on whatever is at the center of the image
+      --blades <n>        Polygonal aperture with <n> blades instead of a round one
+  -e, --exposure <ev>     Exposure adjustment in stops, may be negative (default 0)
+      --tonemap <name>    linear, reinhard, reinhard-extended, aces or agx (default linear)
+      --white-point <l>   Luminance mapped to white by reinhard-extended (default 4)
+      --sampler <name>    independent, stratified, sobol or blue-noise (default independent)
+      --seed <n>          Seed for the sampler; equal seeds give identical images (default 0)
+  -t, --tile-size <n>     Edge length of a render tile in pixels (default 32)
+      --tile-order <o>    scanline, spiral or hilbert (default spiral)
+  -h, --help              Print this message";
+
+pub struct HeadlessOptions {
+    pub scene: Option<PathBuf>,
+    pub output: PathBuf,
+    pub samples: u32,
+    pub width: u32,
+    pub path: PathSettings,
+    pub tiles: TileSettings,
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub tone_mapping: ToneMapping,
+    pub aovs: bool,
+    pub denoiser: Option<DenoiserKind>,
+    pub adaptive: Option<AdaptiveSettings>,
+    pub heatmap: Option<PathBuf>,
+    pub lens: LensOptions,
+}
+
+// Overrides for the scene's camera lens
+#[derive(Default)]
+pub struct LensOptions {
+    pub aperture_radius: Option<f64>,
+    pub focus_distance: Option<f64>,
+    pub autofocus: bool,
+    pub blades: Option<u32>,
+}
+
+pub enum Command {
+    Interactive { scene: Option<PathBuf> },
+    Headless(Box<HeadlessOptions>),
+    Help,
+}
+
+impl Command {
+    pub fn from_args(args: &[String]) -> Result<Command, String> {
+        let mut scene = None;
+        let mut output = None;
+        let mut samples = 64;
+        let mut width = 650;
+        let mut max_depth = 10;
+        let (mut max_diffuse, mut max_specular, mut max_transmission) = (None, None, None);
+        let mut roulette_depth = Some(3);
+        let mut tiles = TileSettings::default();
+        let mut seed = 0;
+        let mut sampler = SamplerKind::default();
+        let mut tone_mapping = ToneMapping::default();
+        let mut aovs = false;
+        let mut denoiser = None;
+        let mut threshold = None;
+        let mut min_samples = AdaptiveSettings::default().min_samples;
+        let mut heatmap = None;
+        let mut lens = LensOptions::default();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-S" | "--scene" => scene = Some(PathBuf::from(Self::value(arg, iter.next())?)),
+                "-o" | "--output" => output = Some(PathBuf::from(Self::value(arg, iter.next())?)),
+                "-s" | "--samples" => samples = Self::number(arg, iter.next())?,
+                "-w" | "--width" => width = Self::number(arg, iter.next())?,
+                "-d" | "--max-depth" => max_depth = Self::number(arg, iter.next())?,
+                "--max-diffuse" => max_diffuse = Some(Self::number(arg, iter.next())?),
+                "--max-specular" => max_specular = Some(Self::number(arg, iter.next())?),
+                "--max-transmission" => max_transmission = Some(Self::number(arg, iter.next())?),
+                "--rr-depth" => roulette_depth = Some(Self::number(arg, iter.next())?),
+                "--no-rr" => roulette_depth = None,
+                "-t" | "--tile-size" => tiles.size = Self::number(arg, iter.next())?,
+                "--seed" => {
+                    let value = Self::value(arg, iter.next())?;
+                    seed = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("'{}' expects a non-negative integer, got '{}'", arg, value))?;
+                }
+                "--aovs" => aovs = true,
+                "--adaptive" => {
+                    let value = Self::float(arg, iter.next())?;
+                    if value <= 0.0 {
+                        return Err(format!("'{}' expects a positive number", arg));
+                    }
+                    threshold = Some(value);
+                }
+                "--min-samples" => min_samples = Self::number(arg, iter.next())?,
+                "--aperture" => {
+                    let value = Self::float(arg, iter.next())?;
+                    if value < 0.0 {
+                        return Err(format!("'{}' must not be negative", arg));
+                    }
+                    lens.aperture_radius = Some(value);
+                }
+                "--focus-distance" => {
+                    let value = Self::float(arg, iter.next())?;
+                    if value <= 0.0 {
+                        return Err(format!("'{}' expects a positive number", arg));
+                    }
+                    lens.focus_distance = Some(value);
+                }
+                "--autofocus" => lens.autofocus = true,
+                "--blades" => {
+                    let value = Self::number(arg, iter.next())?;
+                    if value < 3 {
+                        return Err(format!("'{}' expects at least 3", arg));
+                    }
+                    lens.blades = Some(value);
+                }
+                "--heatmap" => heatmap = Some(PathBuf::from(Self::value(arg, iter.next())?)),
+                "--denoise" => denoiser = Some(Self::value(arg, iter.next())?.parse()?),
+                "-e" | "--exposure" => tone_mapping.exposure = Self::float(arg, iter.next())?,
+                "--tonemap" => tone_mapping.operator = Self::value(arg, iter.next())?.parse()?,
+                "--white-point" => {
+                    tone_mapping.white_point = Self::float(arg, iter.next())?;
+                    if tone_mapping.white_point <= 0.0 {
+                        return Err(format!("'{}' expects a positive number", arg));
+                    }
+                }
+                "--sampler" => sampler = Self::value(arg, iter.next())?.parse()?,
+                "--tile-order" => tiles.order = Self::value(arg, iter.next())?.parse()?,
+                other => return Err(format!("unknown argument '{}'", other)),
+            }
+        }
+
+        let path = PathSettings {
+            max_depth,
+            max_diffuse: max_diffuse.unwrap_or(max_depth),
+            max_specular: max_specular.unwrap_or(max_depth),
+            max_transmission: max_transmission.unwrap_or(max_depth),
+            roulette_depth,
+        };
+
+        let adaptive = threshold.map(|threshold| AdaptiveSettings { threshold, min_samples });
+
+        Ok(match output {
+            Some(output) => Command::Headless(Box::new(HeadlessOptions {
+                scene,
+                output,
+                samples,
+                width,
+                path,
+                tiles,
+                seed,
+                sampler,
+                tone_mapping,
+                aovs,
+                denoiser,
+                adaptive,
+                heatmap,
+                lens,
+            })),
+            None => Command::Interactive { scene },
+        })
+    }
+
+    fn value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+        value
+            .map(|v| v.as_str())
+            .ok_or_else(|| format!("missing value for '{}'", flag))
+    }
+
+    fn float(flag: &str, value: Option<&String>) -> Result<f64, String> {
+        let value = Self::value(flag, value)?;
+        match value.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok(x),
+            _ => Err(format!("'{}' expects a number, got '{}'", flag, value)),
+        }
+    }
+
+    fn number(flag: &str, value: Option<&String>) -> Result<u32, String> {
+        let value = Self::value(flag, value)?;
+        match value.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("'{}' expects a positive integer, got '{}'", flag, value)),
+        }
+    }
+}
+
+impl LensOptions {
+    fn apply(&self, camera: &mut Camera) {
+        if let Some(radius) = self.aperture_radius {
+            camera.set_aperture_radius(radius);
+        }
+        if let Some(distance) = self.focus_distance {
+            camera.set_focus_distance(distance);
+        }
+        if let Some(blades) = self.blades {
+            camera.set_aperture(Aperture::Polygon { blades, rotation: 0.0 });
+        }
+        if self.autofocus {
+            camera.set_autofocus(true);
+        }
+    }
+}
+
+// Loads the scene file if one was given, otherwise the built-in scene
+pub fn load_scene(path: Option<&Path>) -> Result<Scene, String> {
+    match path {
+        Some(path) => Scene::load(path).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => Ok(Scene::default_scene()),
+    }
+}
+
+// Renders the scene to a file without creating a window or touching the GPU
+pub fn render(options: &HeadlessOptions) -> Result<(), String> {
+    // Fail before spending time rendering
+    if !image_writer::is_supported(&options.output) {
+        return Err(format!("unsupported image format for '{}' (expected .png, .ppm, .exr or .pfm)", options.output.display()));
+    }
+    if let Some(heatmap) = options.heatmap.as_ref().filter(|p| !image_writer::is_supported(p) || image_writer::is_hdr(p)) {
+        return Err(format!("unsupported heatmap format for '{}' (expected .png or .ppm)", heatmap.display()));
+    }
+
+    let scene = load_scene(options.scene.as_deref())?;
+    let image_height = renderer::image_height_for(options.width, renderer::DEFAULT_ASPECT_RATIO);
+    let mut camera = Camera::new(&scene.camera, renderer::DEFAULT_ASPECT_RATIO);
+    options.lens.apply(&mut camera);
+    let integrator = Box::new(PathIntegrator::new(options.path));
+    let mut renderer = Renderer::new(options.width, image_height, scene, integrator);
+    renderer.set_tile_settings(options.tiles);
+    renderer.set_seed(options.seed);
+    renderer.set_sampler(options.sampler.with_sample_count(options.samples));
+    renderer.set_tone_mapping(options.tone_mapping);
+    if let Some(denoiser) = options.denoiser {
+        renderer.set_denoiser(denoiser);
+        renderer.set_denoising(true);
+    }
+    renderer.set_adaptive_sampling(options.adaptive);
+    let start = Instant::now();
+
+    if camera.autofocus() {
+        // One frame is enough for the depth at the center
+        renderer.render_progressive(&camera);
+        renderer.autofocus(&mut camera);
+        renderer.reset_accumulation();
+        eprintln!("Autofocus: {:.3}", camera.focus_distance());
+    }
+
+    for sample in 1..=options.samples {
+        renderer.render_tiles(&camera, None, &|progress: TileProgress| {
+            eprint!("\rSamples: {}/{} (tile {}/{})  ", sample, options.samples, progress.completed, progress.total);
+        });
+        if renderer.is_converged() {
+            eprint!("\nAll pixels converged after {} samples", sample);
+            break;
+        }
+    }
+    eprintln!();
+    if let Some(converged) = renderer.converged_fraction() {
+        let pixels = renderer.get_pixel_data();
+        let total: u64 = pixels.iter().map(|p| u64::from(p.sample_count)).sum();
+        eprintln!(
+            "Adaptive sampling: {:.1}% of pixels converged, {:.1} samples per pixel on average",
+            converged * 100.0,
+            total as f64 / pixels.len() as f64
+        );
+    }
+
+    let (width, height) = (renderer.image_width(), renderer.image_height());
+    let failed = |e: std::io::Error| format!("failed to write '{}': {}", options.output.display(), e);
+    if image_writer::is_hdr(&options.output) {
+        let mut aovs = renderer.aovs();
+        if !options.aovs {
+            aovs.truncate(1);
+        }
+        let files = image_writer::write_aovs(&options.output, width, height, &aovs).map_err(failed)?;
+        for file in files.iter().skip(1) {
+            eprintln!("Wrote {}", file.display());
+        }
+    } else {
+        let pixels = renderer.render_rgba();
+        image_writer::write_rgba(&options.output, width, height, &pixels).map_err(failed)?;
+    }
+
+    if let Some(path) = &options.heatmap {
+        image_writer::write_rgba(path, width, height, &renderer.render_heatmap())
+            .map_err(|e| format!("failed to write '{}': {}", path.display(), e))?;
+        eprintln!("Wrote {}", path.display());
+    }
+
+    if let Some(statistics) = renderer.integrator_statistics() {
+        eprintln!("Paths: {}", statistics);
+    }
+    eprintln!(
+        "Wrote {}x{} image to {} in {:.2}s",
+        renderer.image_width(),
+        renderer.image_height(),
+        options.output.display(),
+        start.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
@@ -1 +1,3 @@
-pub mod application;
\ No newline at end of file
+pub mod application;
+pub mod headless;
+pub mod screenshot;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ray_tracer::aperture::Aperture;
+use crate::ray_tracer::camera::Camera;
+use crate::ray_tracer::image_writer;
+use crate::ray_tracer::renderer::Renderer;
+
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+// Saves the accumulated image as `<dir>/screenshot-<time>.png`, a denoised copy next to it
+// and a `.toml` sidecar with the view. The sidecar's [camera] table uses the scene file
+// syntax, so pasting it into the scene reproduces the shot. Returns the raw image's path.
+pub fn save(dir: &Path, camera: &Camera, renderer: &Renderer, scene: Option<&Path>) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let stem = unique_stem(dir, &format!("screenshot-{}", timestamp(SystemTime::now())));
+    let (width, height) = (renderer.image_width(), renderer.image_height());
+
+    let raw = dir.join(format!("{}.png", stem));
+    image_writer::write_png(&raw, width, height, &renderer.render_rgba_with(false))?;
+    image_writer::write_png(&dir.join(format!("{}-denoised.png", stem)), width, height, &renderer.render_rgba_with(true))?;
+    fs::write(dir.join(format!("{}.toml", stem)), sidecar(camera, renderer, scene))?;
+    Ok(raw)
+}
+
+fn sidecar(camera: &Camera, renderer: &Renderer, scene: Option<&Path>) -> String {
+    let mut out = String::new();
+    let p = camera.position;
+    let scene = scene.map_or_else(|| "built-in".to_string(), |path| path.display().to_string());
+    // Writing to a String can't fail
+    let _ = writeln!(out, "# Taken from scene: {}", scene);
+    let _ = writeln!(out, "[camera]");
+    let _ = writeln!(out, "position = [{:?}, {:?}, {:?}]", p.x(), p.y(), p.z());
+    let _ = writeln!(out, "yaw = {:?}", camera.yaw);
+    let _ = writeln!(out, "pitch = {:?}", camera.pitch);
+    let _ = writeln!(out, "fov = {:?}", camera.fov());
+    let _ = writeln!(out, "aperture = {:?}", camera.aperture_radius());
+    let _ = writeln!(out, "focus_distance = {:?}", camera.focus_distance());
+    if camera.autofocus() {
+        let _ = writeln!(out, "autofocus = true");
+    }
+    match camera.aperture() {
+        Aperture::Circle => {}
+        Aperture::Polygon { blades, rotation } => {
+            let _ = writeln!(out, "blades = {}", blades);
+            let _ = writeln!(out, "blade_rotation = {:?}", rotation);
+        }
+        Aperture::Image(image) => {
+            // Absolute, since the scene resolves relative paths against its own directory
+            let path = fs::canonicalize(image.path()).unwrap_or_else(|_| image.path().to_path_buf());
+            let _ = writeln!(out, "aperture_image = {}", toml::Value::String(path.display().to_string()));
+        }
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "[render]");
+    let _ = writeln!(out, "width = {}", renderer.image_width());
+    let _ = writeln!(out, "height = {}", renderer.image_height());
+    let _ = writeln!(out, "samples = {}", renderer.get_sample_count());
+    let _ = writeln!(out, "seed = {}", renderer.seed());
+    out
+}
+
+// Appends -2, -3, ... when several screenshots are taken within a second
+fn unique_stem(dir: &Path, base: &str) -> String {
+    let mut stem = base.to_string();
+    let mut n = 2;
+    while dir.join(format!("{}.png", stem)).exists() {
+        stem = format!("{}-{}", base, n);
+        n += 1;
+    }
+    stem
+}
+
+// UTC time as YYYYMMDD-HHMMSS
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, second_of_day) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        second_of_day / 3600,
+        second_of_day / 60 % 60,
+        second_of_day % 60
+    )
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,5 +1,12 @@
 use crate::app::application::State;
+use crate::app::screenshot;
+use crate::ray_tracer::adaptive::AdaptiveSettings;
 use crate::ray_tracer::camera::Camera;
+use crate::ray_tracer::denoiser::DenoiserKind;
+use crate::ray_tracer::integrator::{PathIntegrator, PathSettings};
+use crate::ray_tracer::renderer::{self, Renderer};
+use crate::ray_tracer::scene::Scene;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use winit::{
@@ -10,22 +17,58 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
-pub fn print_status(fps: u32, denoising_on: bool, cursor_grabbed: bool) {
+// Time spent tracing per redraw before the window gets to handle input again
+const FRAME_BUDGET: Duration = Duration::from_millis(25);
+
+pub fn print_status(
+    fps: u32,
+    raytracer: &Renderer,
+    camera: &Camera,
+    cursor_grabbed: bool,
+    show_heatmap: bool,
+    last_screenshot: Option<&Path>,
+) {
     use std::io::{stdout, Write};
-    // Move cursor up 3 lines, clear lines, go to start
-    print!("\x1B[3F\x1B[0J");
+    let tone_mapping = raytracer.tone_mapping();
+    // Move cursor up 8 lines, clear lines, go to start
+    print!("\x1B[8F\x1B[0J");
     println!("FPS: {:<3}", fps);
-    println!("Denoising: {}", if denoising_on { "ON " } else { "OFF" });
+    match raytracer.integrator_statistics() {
+        Some(paths) => println!("Path length: {:.2} bounces", paths.average_path_length()),
+        None => println!("Path length: -"),
+    }
+    println!(
+        "Denoising: {} with {} filter (T to toggle, F to switch)",
+        if raytracer.is_denoising_enabled() { "ON " } else { "OFF" },
+        raytracer.denoiser()
+    );
+    match raytracer.converged_fraction() {
+        Some(converged) => print!("Adaptive sampling: ON, {:.0}% converged", converged * 100.0),
+        None => print!("Adaptive sampling: OFF"),
+    }
+    println!(" (N to toggle, H for {})", if show_heatmap { "the image" } else { "the sample heatmap" });
+    println!(
+        "Tone mapping: {} at {:+.1} EV (M to cycle, -/= for exposure)",
+        tone_mapping.operator, tone_mapping.exposure
+    );
+    if camera.aperture_radius() > 0.0 {
+        print!("Lens: aperture {:.3}, focus at {:.2}", camera.aperture_radius(), camera.focus_distance());
+    } else {
+        print!("Lens: pinhole");
+    }
+    println!(" ([/] to change, O for autofocus {})", if camera.autofocus() { "ON " } else { "OFF" });
     println!(
         "Mouse: {} (TAB to toggle)",
         if cursor_grabbed { "LOCKED" } else { "FREE  " }
     );
+    match last_screenshot {
+        Some(path) => println!("Screenshot: {} (P to save)", path.display()),
+        None => println!("Screenshot: - (P to save)"),
+    }
     stdout().flush().unwrap();
 }
 
-pub async fn run() {
-    env_logger::init();
-
+pub async fn run(scene: Scene, scene_path: Option<PathBuf>) {
     let event_loop = EventLoop::new().unwrap();
 
     let window: Arc<Window> = Arc::new(
@@ -36,7 +79,10 @@ pub async fn run() {
             .unwrap(),
     );
 
-    let mut raytracer = Camera::new(650, 10);
+    let image_width = 650;
+    let image_height = renderer::image_height_for(image_width, renderer::DEFAULT_ASPECT_RATIO);
+    let mut camera = Camera::new(&scene.camera, renderer::DEFAULT_ASPECT_RATIO);
+    let mut raytracer = Renderer::new(image_width, image_height, scene, Box::new(PathIntegrator::new(PathSettings::default())));
 
     let mut state = State::new(window.as_ref(), &raytracer).await;
 
@@ -52,6 +98,9 @@ pub async fn run() {
     // Mouse handling
     let mut cursor_grabbed = true;
 
+    let mut last_screenshot: Option<PathBuf> = None;
+    let mut show_heatmap = false;
+
     // Initially grab the cursor
     let _ = window
         .set_cursor_grab(winit::window::CursorGrabMode::Confined)
@@ -112,6 +161,133 @@ pub async fn run() {
                         raytracer.toggle_denoising();
                     }
 
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyF),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Switch between the bilateral and a-trous filters on F key press
+                        let next = match raytracer.denoiser() {
+                            DenoiserKind::Bilateral => DenoiserKind::ATrous,
+                            DenoiserKind::ATrous => DenoiserKind::Bilateral,
+                        };
+                        raytracer.set_denoiser(next);
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyN),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Toggle adaptive sampling on N key press
+                        let adaptive = match raytracer.adaptive_sampling() {
+                            Some(_) => None,
+                            None => Some(AdaptiveSettings::default()),
+                        };
+                        raytracer.set_adaptive_sampling(adaptive);
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyH),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Show where the samples went instead of the image on H key press
+                        show_heatmap = !show_heatmap;
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyP),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Save the accumulated image and the view on P key press
+                        let dir = Path::new(screenshot::SCREENSHOT_DIR);
+                        match screenshot::save(dir, &camera, &raytracer, scene_path.as_deref()) {
+                            Ok(path) => last_screenshot = Some(path),
+                            Err(e) => log::error!("failed to save screenshot: {}", e),
+                        }
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyM),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Cycle tone mapping operators on M key press
+                        let mut tone_mapping = raytracer.tone_mapping();
+                        tone_mapping.operator = tone_mapping.operator.next();
+                        raytracer.set_tone_mapping(tone_mapping);
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(key @ (KeyCode::BracketLeft | KeyCode::BracketRight)),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Narrower or wider aperture per press; closing it all the way gives a pinhole
+                        let radius = camera.aperture_radius();
+                        let radius = match key {
+                            KeyCode::BracketRight if radius == 0.0 => 0.01,
+                            KeyCode::BracketRight => radius * 1.5,
+                            _ if radius <= 0.01 => 0.0,
+                            _ => radius / 1.5,
+                        };
+                        camera.set_aperture_radius(radius);
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::KeyO),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Toggle autofocus on O key press
+                        camera.set_autofocus(!camera.autofocus());
+                    }
+
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(key @ (KeyCode::Minus | KeyCode::Equal)),
+                                ..
+                            },
+                        ..
+                    } => {
+                        // Half a stop darker or brighter per press
+                        let mut tone_mapping = raytracer.tone_mapping();
+                        tone_mapping.exposure += if *key == KeyCode::Equal { 0.5 } else { -0.5 };
+                        raytracer.set_tone_mapping(tone_mapping);
+                    }
+
                     WindowEvent::KeyboardInput {
                         event:
                             KeyEvent {
@@ -147,13 +323,18 @@ pub async fn run() {
                         last_frame_time = now;
 
                         // Process continuous input
-                        state.process_continuous_input(&mut raytracer, dt);
+                        state.process_continuous_input(&mut camera, dt);
 
-                        // Progressive rendering - one sample per frame
-                        raytracer.render_progressive();
+                        // Progressive rendering - tiles of one sample per pixel, within a time
+                        // budget so input keeps being handled at high resolutions. Moving the
+                        // camera cancels whatever is left of the frame.
+                        if camera.autofocus() {
+                            raytracer.autofocus(&mut camera);
+                        }
+                        raytracer.render_tiles(&camera, Some(Instant::now() + FRAME_BUDGET), &|_| {});
 
                         state.update();
-                        state.update_image(&raytracer);
+                        state.update_image(&raytracer, show_heatmap);
 
                         match state.render() {
                             Ok(_) => {}
@@ -174,7 +355,7 @@ pub async fn run() {
                         let elapsed = last_time.elapsed();
                         if elapsed >= Duration::from_secs(1) {
                             let fps = frame_count / elapsed.as_secs() as u32;
-                            print_status(fps, raytracer.is_denoising_enabled(), cursor_grabbed);
+                            print_status(fps, &raytracer, &camera, cursor_grabbed, show_heatmap, last_screenshot.as_deref());
                             frame_count = 0;
                             last_time = Instant::now();
                         }
@@ -190,7 +371,7 @@ pub async fn run() {
             Event::DeviceEvent { event, .. } => match event {
                 DeviceEvent::MouseMotion { delta } if cursor_grabbed => {
                     // Process mouse movement for camera rotation
-                    raytracer.process_mouse_movement(delta.0, -delta.1); // Negative Y for natural feel
+                    camera.process_mouse_movement(delta.0, -delta.1); // Negative Y for natural feel
                 }
                 _ => {}
             },
@@ -0,0 +1,3 @@
+pub mod ray_tracer;
+pub mod graphics_pipeline;
+pub mod app;
@@ -1,9 +1,29 @@
-mod ray_tracer;
-mod graphics_pipeline;
-mod app;
-
-use graphics_pipeline::create_window::run;
+use ray_tracer::app::headless::{self, Command};
+use ray_tracer::graphics_pipeline::create_window::run;
 
 fn main() {
-    pollster::block_on(run());
-}
\ No newline at end of file
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match Command::from_args(&args) {
+        Ok(Command::Interactive { scene }) => match headless::load_scene(scene.as_deref()) {
+            Ok(loaded) => pollster::block_on(run(loaded, scene)),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(Command::Headless(options)) => {
+            if let Err(e) = headless::render(&options) {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Ok(Command::Help) => println!("{}", headless::USAGE),
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, headless::USAGE);
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::vec3::Point3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        let mut bbox = Self { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    // Treat the two points as extrema for the bounding box, in any order
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        )
+    }
+
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+
+    #[inline]
+    pub fn axis_interval(&self, n: usize) -> Interval {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    // Returns the index of the longest axis of the bounding box
+    pub fn longest_axis(&self) -> usize {
+        if self.x.size() > self.y.size() {
+            if self.x.size() > self.z.size() { 0 } else { 2 }
+        } else if self.y.size() > self.z.size() {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            0.5 * (self.x.min + self.x.max),
+            0.5 * (self.y.min + self.y.max),
+            0.5 * (self.z.min + self.z.max),
+        )
+    }
+
+    // Surface area used by the SAH cost estimate
+    pub fn surface_area(&self) -> f64 {
+        if self.x.size() < 0.0 || self.y.size() < 0.0 || self.z.size() < 0.0 {
+            return 0.0;
+        }
+        let (dx, dy, dz) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+
+    // Slab test against the ray, narrowing t_range as each axis is visited
+    #[inline]
+    pub fn hit(&self, r: &Ray, mut t_range: Interval) -> bool {
+        let origin = r.origin();
+        let direction = r.direction();
+        let orig = [origin.x(), origin.y(), origin.z()];
+        let dir = [direction.x(), direction.y(), direction.z()];
+
+        for axis in 0..3 {
+            let ax = self.axis_interval(axis);
+            let adinv = 1.0 / dir[axis];
+
+            let t0 = (ax.min - orig[axis]) * adinv;
+            let t1 = (ax.max - orig[axis]) * adinv;
+
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > t_range.min {
+                t_range.min = t0;
+            }
+            if t1 < t_range.max {
+                t_range.max = t1;
+            }
+
+            if t_range.max <= t_range.min {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Adjust the box so that no side is narrower than some delta, padding if necessary
+    fn pad_to_minimums(&mut self) {
+        let delta = 0.0001;
+        if self.x.size() < delta { self.x = self.x.expand(delta); }
+        if self.y.size() < delta { self.y = self.y.expand(delta); }
+        if self.z.size() < delta { self.z = self.z.expand(delta); }
+    }
+
+    pub const EMPTY: Aabb = Aabb {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+}
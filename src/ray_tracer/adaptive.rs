@@ -0,0 +1,39 @@
+use crate::ray_tracer::pixel_data::PixelData;
+
+// Lets pixels stop taking samples once their estimated error is small enough, so the
+// time goes to the pixels that are still noisy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSettings {
+    // Relative standard error below which a pixel counts as converged
+    pub threshold: f64,
+    // Samples every pixel takes before its variance estimate is trusted
+    pub min_samples: u32,
+}
+
+impl Default for AdaptiveSettings {
+    fn default() -> Self {
+        Self { threshold: 0.02, min_samples: 16 }
+    }
+}
+
+impl AdaptiveSettings {
+    pub fn is_converged(&self, pixel: &PixelData) -> bool {
+        pixel.sample_count >= self.min_samples.max(2) && pixel.relative_error() < self.threshold
+    }
+}
+
+// Debug color for `t` in [0, 1], from black through blue, red and yellow to white
+pub fn heatmap_color(t: f64) -> [u8; 4] {
+    const STOPS: [[f64; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.1, 0.1, 0.8],
+        [0.9, 0.1, 0.1],
+        [1.0, 0.9, 0.1],
+        [1.0, 1.0, 1.0],
+    ];
+    let x = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (x as usize).min(STOPS.len() - 2);
+    let f = x - i as f64;
+    let channel = |c: usize| ((STOPS[i][c] * (1.0 - f) + STOPS[i + 1][c] * f) * 255.0 + 0.5) as u8;
+    [channel(0), channel(1), channel(2), 255]
+}
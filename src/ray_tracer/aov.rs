@@ -0,0 +1,19 @@
+// An arbitrary output variable: one image-sized pass of the render, such as the beauty
+// image or a G-buffer, stored as interleaved f32 channels
+pub struct Aov {
+    pub name: &'static str,
+    pub channels: &'static [&'static str],
+    pub data: Vec<f32>,
+}
+
+impl Aov {
+    pub fn new(name: &'static str, channels: &'static [&'static str], data: Vec<f32>) -> Self {
+        debug_assert_eq!(data.len() % channels.len(), 0);
+        Self { name, channels, data }
+    }
+
+    // Values of one channel for every pixel
+    pub fn channel(&self, index: usize) -> Vec<f32> {
+        self.data.iter().skip(index).step_by(self.channels.len()).copied().collect()
+    }
+}
@@ -0,0 +1,122 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ray_tracer::environment::Distribution2D;
+
+// Shape of the lens opening; out-of-focus highlights (bokeh) take this shape
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    #[default]
+    Circle,
+    // Regular polygon formed by the diaphragm blades; rotation is in degrees
+    Polygon { blades: u32, rotation: f64 },
+    // Grayscale mask; brighter pixels let through more light
+    Image(Arc<ApertureImage>),
+}
+
+impl PartialEq for Aperture {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Aperture::Circle, Aperture::Circle) => true,
+            (Aperture::Polygon { blades: a, rotation: r }, Aperture::Polygon { blades: b, rotation: s }) => a == b && r == s,
+            // Masks are compared by identity; comparing pixels every frame would be wasteful
+            (Aperture::Image(a), Aperture::Image(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Aperture {
+    // Maps a uniform sample in [0, 1)^2 to a point on the aperture, in units of the
+    // aperture radius: inside the unit disk, or [-1, 1]^2 for masks
+    pub fn sample(&self, (u1, u2): (f64, f64)) -> (f64, f64) {
+        match self {
+            Aperture::Circle => concentric_disk(u1, u2),
+            Aperture::Polygon { blades, rotation } => {
+                // Pick one of the triangles between the center and two neighbouring corners,
+                // then a uniform point in it
+                let n = (*blades).max(3) as f64;
+                let scaled = u1 * n;
+                let k = scaled.floor().min(n - 1.0);
+                let r = (scaled - k).sqrt();
+                let corner = |i: f64| {
+                    let angle = rotation.to_radians() + 2.0 * PI * i / n;
+                    (angle.cos(), angle.sin())
+                };
+                let (a, b) = (corner(k), corner(k + 1.0));
+                (r * ((1.0 - u2) * a.0 + u2 * b.0), r * ((1.0 - u2) * a.1 + u2 * b.1))
+            }
+            Aperture::Image(image) => image.sample(u1, u2),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ApertureError(String);
+
+impl fmt::Display for ApertureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ApertureError {}
+
+// Aperture mask, importance sampled by brightness
+pub struct ApertureImage {
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    distribution: Distribution2D,
+}
+
+impl fmt::Debug for ApertureImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ApertureImage({}x{})", self.width, self.height)
+    }
+}
+
+impl ApertureImage {
+    pub fn load(path: &Path) -> Result<Self, ApertureError> {
+        let image = image::open(path)
+            .map_err(|e| ApertureError(format!("{}: {}", path.display(), e)))?
+            .to_luma32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let weights: Vec<f64> = image.pixels().map(|p| p[0].max(0.0) as f64).collect();
+        if !weights.iter().any(|&w| w > 0.0) {
+            return Err(ApertureError(format!("{}: the mask is completely black", path.display())));
+        }
+        Ok(Self { path: path.to_path_buf(), width, height, distribution: Distribution2D::new(&weights, width, height) })
+    }
+
+    // File the mask was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The image is fitted into [-1, 1]^2, keeping its aspect ratio, with its top facing up
+    fn sample(&self, u1: f64, u2: f64) -> (f64, f64) {
+        let Some((u, v, _)) = self.distribution.sample(u1, u2) else {
+            return (0.0, 0.0);
+        };
+        let size = self.width.max(self.height) as f64;
+        let (sx, sy) = (self.width as f64 / size, self.height as f64 / size);
+        ((2.0 * u - 1.0) * sx, (1.0 - 2.0 * v) * sy)
+    }
+}
+
+// Shirley and Chiu's mapping, which keeps strata of the square compact on the disk
+fn concentric_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
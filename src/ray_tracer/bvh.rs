@@ -0,0 +1,191 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::hittable_list::HittableList;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::aabb::Aabb;
+
+const SAH_BUCKETS: usize = 12;
+const TRAVERSAL_COST: f64 = 0.125;
+
+type Primitive = (Arc<dyn Hittable>, Aabb, u32);
+
+pub struct BvhNode {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    // Position in the source list of a child that is an object rather than another node,
+    // written to the hit record as its object id
+    left_id: Option<u32>,
+    right_id: Option<u32>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let primitives = list
+            .into_objects()
+            .into_iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let bbox = object.bounding_box();
+                (object, bbox, i as u32)
+            })
+            .collect();
+        Self::build(primitives)
+    }
+
+    fn build(mut primitives: Vec<Primitive>) -> Self {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, (_, b, _)| Aabb::surrounding(&acc, b));
+
+        match primitives.len() {
+            0 => {
+                let empty: Arc<dyn Hittable> = Arc::new(HittableList::new());
+                return Self { left: Arc::clone(&empty), right: empty, left_id: None, right_id: None, bbox };
+            }
+            1 => {
+                let (only, _, id) = primitives.pop().unwrap();
+                return Self { left: Arc::clone(&only), right: only, left_id: Some(id), right_id: Some(id), bbox };
+            }
+            2 => {
+                let (right, _, right_id) = primitives.pop().unwrap();
+                let (left, _, left_id) = primitives.pop().unwrap();
+                return Self { left, right, left_id: Some(left_id), right_id: Some(right_id), bbox };
+            }
+            _ => {}
+        }
+
+        let mid = Self::partition_sah(&mut primitives, &bbox);
+        let right_half = primitives.split_off(mid);
+
+        let (left, left_id) = Self::build_child(primitives);
+        let (right, right_id) = Self::build_child(right_half);
+        Self { left, right, left_id, right_id, bbox }
+    }
+
+    fn build_child(mut primitives: Vec<Primitive>) -> (Arc<dyn Hittable>, Option<u32>) {
+        if primitives.len() == 1 {
+            let (object, _, id) = primitives.pop().unwrap();
+            (object, Some(id))
+        } else {
+            (Arc::new(Self::build(primitives)), None)
+        }
+    }
+
+    // Reorders the primitives around the cheapest binned SAH split and returns the split index.
+    // Falls back to a median split along the longest axis when the centroids are degenerate
+    // or no split beats the cost of a leaf.
+    fn partition_sah(primitives: &mut [Primitive], bbox: &Aabb) -> usize {
+        let centroid_bounds = primitives.iter().fold(Aabb::EMPTY, |acc, (_, b, _)| {
+            let c = b.centroid();
+            Aabb::surrounding(&acc, &Aabb::from_points(c, c))
+        });
+
+        let parent_area = bbox.surface_area();
+        let leaf_cost = primitives.len() as f64;
+        let mut best: Option<(usize, usize, f64)> = None; // (axis, bucket, cost)
+
+        for axis in 0..3 {
+            let extent = centroid_bounds.axis_interval(axis);
+            if extent.size() <= 1e-9 {
+                continue;
+            }
+
+            let mut counts = [0usize; SAH_BUCKETS];
+            let mut bounds = [Aabb::EMPTY; SAH_BUCKETS];
+            for (_, b, _) in primitives.iter() {
+                let i = Self::bucket_index(b, axis, extent);
+                counts[i] += 1;
+                bounds[i] = Aabb::surrounding(&bounds[i], b);
+            }
+
+            // Sweep from the right to get the suffix areas, then from the left to evaluate each split
+            let mut right_area = [0.0; SAH_BUCKETS];
+            let mut right_count = [0usize; SAH_BUCKETS];
+            let mut acc_box = Aabb::EMPTY;
+            let mut acc_count = 0;
+            for i in (1..SAH_BUCKETS).rev() {
+                acc_box = Aabb::surrounding(&acc_box, &bounds[i]);
+                acc_count += counts[i];
+                right_area[i] = acc_box.surface_area();
+                right_count[i] = acc_count;
+            }
+
+            let mut left_box = Aabb::EMPTY;
+            let mut left_count = 0;
+            for split in 1..SAH_BUCKETS {
+                left_box = Aabb::surrounding(&left_box, &bounds[split - 1]);
+                left_count += counts[split - 1];
+                if left_count == 0 || right_count[split] == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST
+                    + (left_box.surface_area() * left_count as f64
+                        + right_area[split] * right_count[split] as f64)
+                        / parent_area.max(f64::MIN_POSITIVE);
+
+                if best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    best = Some((axis, split, cost));
+                }
+            }
+        }
+
+        match best {
+            Some((axis, split, cost)) if cost < leaf_cost => {
+                let extent = centroid_bounds.axis_interval(axis);
+                let mut mid = 0;
+                for i in 0..primitives.len() {
+                    if Self::bucket_index(&primitives[i].1, axis, extent) < split {
+                        primitives.swap(i, mid);
+                        mid += 1;
+                    }
+                }
+                mid
+            }
+            _ => {
+                let axis = bbox.longest_axis();
+                primitives.sort_by(|a, b| {
+                    let ca = a.1.axis_interval(axis).min;
+                    let cb = b.1.axis_interval(axis).min;
+                    ca.total_cmp(&cb)
+                });
+                primitives.len() / 2
+            }
+        }
+    }
+
+    #[inline]
+    fn bucket_index(b: &Aabb, axis: usize, extent: Interval) -> usize {
+        let c = b.axis_interval(axis);
+        let centroid = 0.5 * (c.min + c.max);
+        let i = ((centroid - extent.min) / extent.size() * SAH_BUCKETS as f64) as usize;
+        i.min(SAH_BUCKETS - 1)
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, t_range) {
+            return false;
+        }
+
+        let hit_left = self.left.hit(r, t_range, rec);
+        if let (true, Some(id)) = (hit_left, self.left_id) {
+            rec.object_id = id;
+        }
+        let right_max = if hit_left { rec.t } else { t_range.max };
+        let hit_right = self.right.hit(r, Interval::new(t_range.min, right_max), rec);
+        if let (true, Some(id)) = (hit_right, self.right_id) {
+            rec.object_id = id;
+        }
+
+        hit_left || hit_right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
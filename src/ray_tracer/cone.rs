@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::cylinder::{turn_fraction, AxisFrame};
+use crate::ray_tracer::disk::disk_bounds;
+
+// Closed cone with a round base of `radius` at `base` and its apex at `base + axis`. UVs
+// on the side are the angle around the axis and the height fraction; on the base the
+// angle and the distance from the center.
+pub struct Cone {
+    frame: AxisFrame,
+    height: f64,
+    radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Cone {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let frame = AxisFrame::new(base, axis);
+        let apex = base + axis;
+        let bbox = Aabb::surrounding(&disk_bounds(base, frame.z, radius), &Aabb::from_points(apex, apex));
+        Cone { frame, height: axis.length(), radius, material, bbox }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let (o, d) = self.frame.to_local(r);
+        // The side is x^2 + y^2 = k^2 (h - z)^2 for z in [0, h]
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let q = self.height - o[2];
+
+        let mut best: Option<(f64, [f64; 3], f64, f64)> = None;
+        let mut consider = |t: f64, normal: [f64; 3], u: f64, v: f64| {
+            if t_range.contains(t) && best.is_none_or(|(closest, ..)| t < closest) {
+                best = Some((t, normal, u, v));
+            }
+        };
+
+        let a = d[0] * d[0] + d[1] * d[1] - k2 * d[2] * d[2];
+        let half_b = o[0] * d[0] + o[1] * d[1] + k2 * q * d[2];
+        let c = o[0] * o[0] + o[1] * o[1] - k2 * q * q;
+        let roots = if a.abs() < 1e-12 {
+            // Parallel to the slant: a single crossing
+            if half_b.abs() < 1e-12 { vec![] } else { vec![-c / (2.0 * half_b)] }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant < 0.0 {
+                vec![]
+            } else {
+                let sqrtd = discriminant.sqrt();
+                vec![(-half_b - sqrtd) / a, (-half_b + sqrtd) / a]
+            }
+        };
+        for t in roots {
+            let p = [o[0] + t * d[0], o[1] + t * d[1], o[2] + t * d[2]];
+            // The other nappe of the double cone lies above the apex
+            if (0.0..=self.height).contains(&p[2]) {
+                let normal = [p[0], p[1], k2 * (self.height - p[2])];
+                let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+                // At the apex itself the side has no normal; use the axis
+                let normal = if length > 1e-12 { normal.map(|n| n / length) } else { [0.0, 0.0, 1.0] };
+                consider(t, normal, turn_fraction(p[0], p[1]), p[2] / self.height);
+            }
+        }
+
+        if d[2].abs() > 1e-12 {
+            let t = -o[2] / d[2];
+            let (x, y) = (o[0] + t * d[0], o[1] + t * d[1]);
+            let distance_squared = x * x + y * y;
+            if distance_squared <= self.radius * self.radius {
+                consider(t, [0.0, 0.0, -1.0], turn_fraction(x, y), distance_squared.sqrt() / self.radius);
+            }
+        }
+
+        let Some((t, normal, u, v)) = best else {
+            return false;
+        };
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_outward_normal(r, self.frame.to_world(normal));
+        // Around the axis, the way u grows
+        let (x, y) = (o[0] + t * d[0], o[1] + t * d[1]);
+        rec.set_tangent(self.frame.to_world([-y, x, 0.0]));
+        rec.u = u;
+        rec.v = v;
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+
+// Solid box with its own orthonormal axes; axis-aligned boxes use the world axes. UVs run
+// from 0 to 1 across each face.
+pub struct Cuboid {
+    center: Point3,
+    axes: [Vec3; 3],
+    half_size: [f64; 3],
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Cuboid {
+    // Axis-aligned box between two opposite corners
+    pub fn new(a: Point3, b: Point3, material: Arc<dyn Material>) -> Self {
+        let axes = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)];
+        let size = b - a;
+        let half_size = [size.x().abs() / 2.0, size.y().abs() / 2.0, size.z().abs() / 2.0];
+        Self::oriented((a + b) / 2.0, half_size, axes, material)
+    }
+
+    // Box around `center` reaching half_size[i] along axes[i] in both directions. The
+    // axes must be perpendicular to each other; they are normalized here.
+    pub fn oriented(center: Point3, half_size: [f64; 3], axes: [Vec3; 3], material: Arc<dyn Material>) -> Self {
+        let axes = axes.map(|a| Vec3::unit_vector(&a));
+        let extent = |e: Vec3| (0..3).map(|i| half_size[i] * Vec3::dot(axes[i], e).abs()).sum::<f64>();
+        let reach = Vec3::new(
+            extent(Vec3::new(1.0, 0.0, 0.0)),
+            extent(Vec3::new(0.0, 1.0, 0.0)),
+            extent(Vec3::new(0.0, 0.0, 1.0)),
+        );
+        let bbox = Aabb::from_points(center - reach, center + reach);
+        Cuboid { center, axes, half_size, material, bbox }
+    }
+}
+
+impl Hittable for Cuboid {
+    // Slab test in the box's own frame, remembering which slab was entered and left last
+    #[inline]
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let offset = r.origin() - self.center;
+        let o = self.axes.map(|a| Vec3::dot(offset, a));
+        let d = self.axes.map(|a| Vec3::dot(r.direction(), a));
+
+        let (mut t_near, mut t_far) = (f64::NEG_INFINITY, f64::INFINITY);
+        let (mut near_axis, mut far_axis) = (0, 0);
+        for i in 0..3 {
+            if d[i].abs() < 1e-12 {
+                // Parallel to this slab: inside it for the whole ray or never
+                if o[i].abs() > self.half_size[i] {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (-self.half_size[i] - o[i]) / d[i];
+            let t1 = (self.half_size[i] - o[i]) / d[i];
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = i;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = i;
+            }
+        }
+        if t_near > t_far {
+            return false;
+        }
+
+        // The entry point, or the exit point when the ray starts inside
+        let (t, axis, sign) = if t_range.contains(t_near) {
+            (t_near, near_axis, -d[near_axis].signum())
+        } else if t_range.contains(t_far) {
+            (t_far, far_axis, d[far_axis].signum())
+        } else {
+            return false;
+        };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_outward_normal(r, self.axes[axis] * sign);
+        let (i, j) = ((axis + 1) % 3, (axis + 2) % 3);
+        rec.set_tangent(self.axes[i]);
+        let face = |k: usize| ((o[k] + t * d[k]) / self.half_size[k].max(1e-12) + 1.0) / 2.0;
+        rec.u = face(i).clamp(0.0, 1.0);
+        rec.v = face(j).clamp(0.0, 1.0);
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,131 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::disk::disk_bounds;
+use crate::ray_tracer::light::orthonormal_basis;
+
+// Local frame of a shape built around an axis: x and y across it, z along it from `origin`
+pub struct AxisFrame {
+    pub origin: Point3,
+    pub x: Vec3,
+    pub y: Vec3,
+    pub z: Vec3,
+}
+
+impl AxisFrame {
+    pub fn new(origin: Point3, axis: Vec3) -> Self {
+        let z = Vec3::unit_vector(&axis);
+        let (x, y) = orthonormal_basis(z);
+        Self { origin, x, y, z }
+    }
+
+    // Origin and direction of the ray in this frame, as arrays of x, y, z
+    #[inline]
+    pub fn to_local(&self, r: &Ray) -> ([f64; 3], [f64; 3]) {
+        let o = r.origin() - self.origin;
+        let d = r.direction();
+        (
+            [Vec3::dot(o, self.x), Vec3::dot(o, self.y), Vec3::dot(o, self.z)],
+            [Vec3::dot(d, self.x), Vec3::dot(d, self.y), Vec3::dot(d, self.z)],
+        )
+    }
+
+    #[inline]
+    pub fn to_world(&self, v: [f64; 3]) -> Vec3 {
+        self.x * v[0] + self.y * v[1] + self.z * v[2]
+    }
+}
+
+// Angle around the frame's z axis as a fraction of a turn
+#[inline]
+pub fn turn_fraction(x: f64, y: f64) -> f64 {
+    y.atan2(x).rem_euclid(2.0 * PI) / (2.0 * PI)
+}
+
+// Closed cylinder from `base` to `base + axis`. UVs on the side are the angle around the
+// axis and the height fraction; on the caps the angle and the distance from the center.
+pub struct Cylinder {
+    frame: AxisFrame,
+    height: f64,
+    radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, axis: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let frame = AxisFrame::new(base, axis);
+        let bbox = Aabb::surrounding(
+            &disk_bounds(base, frame.z, radius),
+            &disk_bounds(base + axis, frame.z, radius),
+        );
+        Cylinder { frame, height: axis.length(), radius, material, bbox }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let (o, d) = self.frame.to_local(r);
+        let radius_squared = self.radius * self.radius;
+
+        // Nearest of the side and both caps: (t, local outward normal, u, v)
+        let mut best: Option<(f64, [f64; 3], f64, f64)> = None;
+        let mut consider = |t: f64, normal: [f64; 3], u: f64, v: f64| {
+            if t_range.contains(t) && best.is_none_or(|(closest, ..)| t < closest) {
+                best = Some((t, normal, u, v));
+            }
+        };
+
+        let a = d[0] * d[0] + d[1] * d[1];
+        if a > 1e-12 {
+            let half_b = o[0] * d[0] + o[1] * d[1];
+            let c = o[0] * o[0] + o[1] * o[1] - radius_squared;
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let sqrtd = discriminant.sqrt();
+                for t in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+                    let p = [o[0] + t * d[0], o[1] + t * d[1], o[2] + t * d[2]];
+                    if (0.0..=self.height).contains(&p[2]) {
+                        let normal = [p[0] / self.radius, p[1] / self.radius, 0.0];
+                        consider(t, normal, turn_fraction(p[0], p[1]), p[2] / self.height);
+                    }
+                }
+            }
+        }
+
+        if d[2].abs() > 1e-12 {
+            for (z, facing) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (z - o[2]) / d[2];
+                let (x, y) = (o[0] + t * d[0], o[1] + t * d[1]);
+                let distance_squared = x * x + y * y;
+                if distance_squared <= radius_squared {
+                    consider(t, [0.0, 0.0, facing], turn_fraction(x, y), distance_squared.sqrt() / self.radius);
+                }
+            }
+        }
+
+        let Some((t, normal, u, v)) = best else {
+            return false;
+        };
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_outward_normal(r, self.frame.to_world(normal));
+        // Around the axis, the way u grows
+        let (x, y) = (o[0] + t * d[0], o[1] + t * d[1]);
+        rec.set_tangent(self.frame.to_world([-y, x, 0.0]));
+        rec.u = u;
+        rec.v = v;
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
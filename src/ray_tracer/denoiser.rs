@@ -1,8 +1,56 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ray_tracer::environment::luminance;
 use crate::ray_tracer::pixel_data::PixelData;
 use crate::ray_tracer::vec3::{Color, Vec3};
 use rayon::prelude::*;
 
-pub struct Denoiser {
+// Filters the noisy image guided by the G-buffer. Takes the raw accumulation buffer and
+// returns the mean color of every pixel, denoised.
+pub trait Denoiser: Send + Sync {
+    fn denoise(&self, pixel_data: &[PixelData]) -> Vec<Color>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DenoiserKind {
+    #[default]
+    Bilateral,
+    ATrous,
+}
+
+impl DenoiserKind {
+    pub fn create(self, width: u32, height: u32) -> Box<dyn Denoiser> {
+        match self {
+            DenoiserKind::Bilateral => Box::new(BilateralDenoiser::new(width, height)),
+            DenoiserKind::ATrous => Box::new(ATrousDenoiser::new(width, height, 5)),
+        }
+    }
+}
+
+impl FromStr for DenoiserKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bilateral" => Ok(DenoiserKind::Bilateral),
+            "atrous" => Ok(DenoiserKind::ATrous),
+            _ => Err(format!("unknown denoiser '{}' (expected bilateral or atrous)", s)),
+        }
+    }
+}
+
+impl fmt::Display for DenoiserKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DenoiserKind::Bilateral => write!(f, "bilateral"),
+            DenoiserKind::ATrous => write!(f, "atrous"),
+        }
+    }
+}
+
+// Fixed 5x5 joint bilateral filter on color, depth and normal
+pub struct BilateralDenoiser {
     width: u32,
     height: u32,
     kernel_radius: i32,
@@ -11,7 +59,7 @@ pub struct Denoiser {
     inv_sigma_depth: f64,
 }
 
-impl Denoiser {
+impl BilateralDenoiser {
     pub fn new(width: u32, height: u32) -> Self {
         let kernel_radius = 2;
         let spatial_kernel = Self::make_spatial_kernel(kernel_radius);
@@ -25,19 +73,6 @@ impl Denoiser {
         }
     }
 
-    pub fn denoise(&self, pixel_data: &[PixelData]) -> Vec<Color> {
-        (0..self.height)
-            .into_par_iter()
-            .flat_map_iter(|y| {
-                let mut row = Vec::with_capacity(self.width as usize);
-                for x in 0..self.width {
-                    row.push(self.denoise_pixel(x, y, pixel_data));
-                }
-                row
-            })
-            .collect()
-    }
-
     fn denoise_pixel(&self, x: u32, y: u32, pixel_data: &[PixelData]) -> Color {
         let center_idx = self.get_pixel_index(x, y);
         let center = pixel_data[center_idx];
@@ -59,12 +94,12 @@ impl Denoiser {
                 let spatial_weight = self.spatial_kernel[(ky + self.kernel_radius) as usize][(kx + self.kernel_radius) as usize];
 
                 // Feature weights
-                let color_weight = self.calculate_color_similarity(center.color, neighbor.color);
+                let color_weight = self.calculate_color_similarity(center.mean(), neighbor.mean());
                 let depth_weight = self.calculate_depth_similarity(center.depth as f64, neighbor.depth as f64);
                 let normal_weight = self.calculate_normal_similarity(center.normal, neighbor.normal);
 
                 let w = spatial_weight * color_weight * depth_weight * normal_weight;
-                result = result + neighbor.color * w;
+                result += neighbor.mean() * w;
                 total_weight += w;
             }
         }
@@ -72,7 +107,7 @@ impl Denoiser {
         if total_weight > 0.0 {
             result / total_weight
         } else {
-            center.color
+            center.mean()
         }
     }
 
@@ -115,4 +150,206 @@ impl Denoiser {
         }
         Vec3::dot(n1, n2).max(0.0)
     }
-}
\ No newline at end of file
+}
+
+impl Denoiser for BilateralDenoiser {
+    fn denoise(&self, pixel_data: &[PixelData]) -> Vec<Color> {
+        (0..self.height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let mut row = Vec::with_capacity(self.width as usize);
+                for x in 0..self.width {
+                    row.push(self.denoise_pixel(x, y, pixel_data));
+                }
+                row
+            })
+            .collect()
+    }
+}
+
+// Edge-avoiding a-trous wavelet filter as in SVGF (Schied et al. 2017). A 5x5 B3-spline
+// kernel is applied repeatedly with its taps spread 1, 2, 4, ... pixels apart, so a few
+// cheap passes cover a large footprint. Lighting is filtered without the surface albedo
+// (demodulated) so texture detail survives, and the color weight is scaled by the
+// estimated standard deviation of each pixel, so noisy pixels are blurred more.
+pub struct ATrousDenoiser {
+    width: u32,
+    height: u32,
+    iterations: u32,
+    // Luminance, normal and depth edge-stopping parameters
+    sigma_luminance: f64,
+    sigma_normal: f64,
+    sigma_depth: f64,
+}
+
+// Working values of one pixel between passes
+#[derive(Clone, Copy)]
+struct ATrousPixel {
+    irradiance: Color,
+    variance: f64,
+}
+
+const B3_SPLINE: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+impl ATrousDenoiser {
+    pub fn new(width: u32, height: u32, iterations: u32) -> Self {
+        Self { width, height, iterations, sigma_luminance: 4.0, sigma_normal: 128.0, sigma_depth: 1.0 }
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y as u32 * self.width + x as u32) as usize
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
+    }
+
+    // Largest depth change to a direct neighbour, used to tell slopes from edges
+    fn depth_gradients(&self, pixel_data: &[PixelData]) -> Vec<f64> {
+        (0..self.height as i32)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                (0..self.width as i32).map(move |x| {
+                    let depth = pixel_data[self.index(x, y)].depth as f64;
+                    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                        .iter()
+                        .filter(|(dx, dy)| self.in_bounds(x + dx, y + dy))
+                        .map(|(dx, dy)| (pixel_data[self.index(x + dx, y + dy)].depth as f64 - depth).abs())
+                        .filter(|d| d.is_finite())
+                        .fold(0.0, f64::max)
+                })
+            })
+            .collect()
+    }
+
+    // Variance blurred over 3x3, which makes the color weights far more stable
+    fn prefiltered_variance(&self, pixels: &[ATrousPixel]) -> Vec<f64> {
+        const GAUSSIAN: [f64; 3] = [0.25, 0.5, 0.25];
+        (0..self.height as i32)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                (0..self.width as i32).map(move |x| {
+                    let (mut sum, mut total) = (0.0, 0.0);
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            if !self.in_bounds(x + dx, y + dy) {
+                                continue;
+                            }
+                            let w = GAUSSIAN[(dx + 1) as usize] * GAUSSIAN[(dy + 1) as usize];
+                            sum += pixels[self.index(x + dx, y + dy)].variance * w;
+                            total += w;
+                        }
+                    }
+                    sum / total
+                })
+            })
+            .collect()
+    }
+
+    fn pass(&self, pixels: &[ATrousPixel], pixel_data: &[PixelData], gradients: &[f64], step: i32) -> Vec<ATrousPixel> {
+        let variance = self.prefiltered_variance(pixels);
+        (0..self.height as i32)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let variance = &variance;
+                (0..self.width as i32).map(move |x| {
+                    let center_index = self.index(x, y);
+                    let center = pixels[center_index];
+                    let center_data = &pixel_data[center_index];
+                    let center_luminance = luminance(center.irradiance);
+                    let luminance_scale = self.sigma_luminance * variance[center_index].max(0.0).sqrt() + 1e-6;
+
+                    let mut sum = Color::new(0.0, 0.0, 0.0);
+                    let mut sum_variance = 0.0;
+                    let mut total = 0.0;
+                    for (j, ky) in B3_SPLINE.iter().enumerate() {
+                        for (i, kx) in B3_SPLINE.iter().enumerate() {
+                            let (ox, oy) = ((i as i32 - 2) * step, (j as i32 - 2) * step);
+                            let (nx, ny) = (x + ox, y + oy);
+                            if !self.in_bounds(nx, ny) {
+                                continue;
+                            }
+                            let index = self.index(nx, ny);
+                            let neighbor = pixels[index];
+                            let data = &pixel_data[index];
+
+                            let w_luminance = (-(center_luminance - luminance(neighbor.irradiance)).abs() / luminance_scale).exp();
+                            let w_normal = self.normal_weight(center_data.normal, data.normal);
+                            let distance = ((ox * ox + oy * oy) as f64).sqrt();
+                            let w_depth = self.depth_weight(center_data.depth as f64, data.depth as f64, gradients[center_index] * distance);
+
+                            let w = kx * ky * w_luminance * w_normal * w_depth;
+                            sum += neighbor.irradiance * w;
+                            sum_variance += w * w * neighbor.variance;
+                            total += w;
+                        }
+                    }
+
+                    if total > 0.0 {
+                        ATrousPixel { irradiance: sum / total, variance: sum_variance / (total * total) }
+                    } else {
+                        center
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn normal_weight(&self, n1: Vec3, n2: Vec3) -> f64 {
+        if n1.near_zero() || n2.near_zero() {
+            return 1.0;
+        }
+        Vec3::dot(n1, n2).max(0.0).powf(self.sigma_normal)
+    }
+
+    #[inline]
+    fn depth_weight(&self, d1: f64, d2: f64, expected_change: f64) -> f64 {
+        // Both escaped to the environment, or only one of them did
+        if !d1.is_finite() || !d2.is_finite() {
+            return if d1.is_finite() == d2.is_finite() { 1.0 } else { 0.0 };
+        }
+        (-(d1 - d2).abs() / (self.sigma_depth * expected_change + 1e-3)).exp()
+    }
+}
+
+// Albedo the lighting is divided by; pixels without one (the sky) are filtered as they are
+fn demodulation_albedo(p: &PixelData) -> Color {
+    let a = p.albedo;
+    let channel = |c: f64| if c > 1e-3 { c } else { 1.0 };
+    if p.depth.is_finite() {
+        Color::new(channel(a.x()), channel(a.y()), channel(a.z()))
+    } else {
+        Color::new(1.0, 1.0, 1.0)
+    }
+}
+
+impl Denoiser for ATrousDenoiser {
+    fn denoise(&self, pixel_data: &[PixelData]) -> Vec<Color> {
+        let mut pixels: Vec<ATrousPixel> = pixel_data
+            .par_iter()
+            .map(|p| {
+                let albedo = demodulation_albedo(p);
+                let scale = luminance(albedo);
+                let mean = p.mean();
+                ATrousPixel {
+                    irradiance: Color::new(mean.x() / albedo.x(), mean.y() / albedo.y(), mean.z() / albedo.z()),
+                    // Variance of the pixel's mean, in demodulated units
+                    variance: luminance(p.variance()) / p.sample_count.max(1) as f64 / (scale * scale),
+                }
+            })
+            .collect();
+
+        let gradients = self.depth_gradients(pixel_data);
+        for i in 0..self.iterations {
+            pixels = self.pass(&pixels, pixel_data, &gradients, 1 << i);
+        }
+
+        pixels
+            .par_iter()
+            .zip(pixel_data.par_iter())
+            .map(|(p, data)| p.irradiance * demodulation_albedo(data))
+            .collect()
+    }
+}
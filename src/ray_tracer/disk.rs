@@ -0,0 +1,76 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::light::orthonormal_basis;
+
+// Flat circle around `center`, facing `normal`. UVs are polar: u the angle as a fraction
+// of a turn, v the distance from the center as a fraction of the radius.
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        let normal = Vec3::unit_vector(&normal);
+        let (tangent, bitangent) = orthonormal_basis(normal);
+        Disk { center, normal, radius, tangent, bitangent, material, bbox: disk_bounds(center, normal, radius) }
+    }
+}
+
+// Tight box around a disk: along each axis it reaches radius * sin(angle to the normal)
+pub fn disk_bounds(center: Point3, normal: Vec3, radius: f64) -> Aabb {
+    let extent = |n: f64| radius * (1.0 - n * n).max(0.0).sqrt();
+    let e = Vec3::new(extent(normal.x()), extent(normal.y()), extent(normal.z()));
+    Aabb::from_points(center - e, center + e)
+}
+
+impl Hittable for Disk {
+    #[inline]
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let denom = Vec3::dot(self.normal, r.direction());
+
+        // Ray is parallel to the disk
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+
+        let t = Vec3::dot(self.center - r.origin(), self.normal) / denom;
+        if t < t_range.min || t > t_range.max {
+            return false;
+        }
+
+        let p = r.at(t);
+        let planar = p - self.center;
+        let distance_squared = planar.length_squared();
+        if distance_squared > self.radius * self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.set_outward_normal(r, self.normal);
+        // Around the center, the way the angle grows
+        rec.set_tangent(Vec3::cross(self.normal, planar));
+        let angle = Vec3::dot(planar, self.bitangent).atan2(Vec3::dot(planar, self.tangent));
+        rec.u = angle.rem_euclid(2.0 * PI) / (2.0 * PI);
+        rec.v = distance_squared.sqrt() / self.radius;
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
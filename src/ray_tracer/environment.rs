@@ -0,0 +1,319 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::ray_tracer::light::{Light, LightSample};
+use crate::ray_tracer::sampler::Sampler;
+use crate::ray_tracer::vec3::{Color, Point3, Vec3};
+
+// Radiance arriving from infinitely far away, seen by every ray that leaves the scene
+pub trait Environment: Send + Sync {
+    fn radiance(&self, direction: Vec3) -> Color;
+
+    // Picks a unit direction for light sampling, returning it with its solid angle density.
+    // Environments that don't support importance sampling return None.
+    fn sample(&self, _sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    fn pdf(&self, _direction: Vec3) -> f64 {
+        0.0
+    }
+}
+
+// The original white-to-blue sky, blended on the ray's y direction
+pub struct GradientEnvironment {
+    horizon: Color,
+    zenith: Color,
+}
+
+impl GradientEnvironment {
+    pub fn new(horizon: Color, zenith: Color) -> Self {
+        Self { horizon, zenith }
+    }
+}
+
+impl Default for GradientEnvironment {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn radiance(&self, direction: Vec3) -> Color {
+        let unit_direction = Vec3::unit_vector(&direction);
+        let t = 0.5 * (unit_direction.y() + 1.0);
+        self.horizon * (1.0 - t) + self.zenith * t
+    }
+}
+
+// Same radiance from every direction; black makes a closed studio
+pub struct UniformEnvironment {
+    color: Color,
+}
+
+impl UniformEnvironment {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Environment for UniformEnvironment {
+    fn radiance(&self, _direction: Vec3) -> Color {
+        self.color
+    }
+}
+
+#[derive(Debug)]
+pub struct EnvironmentError(String);
+
+impl fmt::Display for EnvironmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for EnvironmentError {}
+
+// Equirectangular (latitude-longitude) HDR image, importance sampled by luminance
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    // Rotation around the Y axis in radians
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    // Loads a Radiance .hdr or OpenEXR file. Rotation is in degrees around the Y axis.
+    pub fn load(path: &Path, rotation: f64, intensity: f64) -> Result<Self, EnvironmentError> {
+        let image = image::open(path)
+            .map_err(|e| EnvironmentError(format!("{}: {}", path.display(), e)))?
+            .into_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            .collect();
+        Self::from_pixels(width, height, pixels, rotation, intensity)
+            .map_err(|e| EnvironmentError(format!("{}: {}", path.display(), e)))
+    }
+
+    // Pixels are in rows from the top, `width` to a row
+    pub fn from_pixels(
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+        rotation: f64,
+        intensity: f64,
+    ) -> Result<Self, EnvironmentError> {
+        if width == 0 || height == 0 {
+            return Err(EnvironmentError(format!("the image is empty ({}x{})", width, height)));
+        }
+        if pixels.len() != width * height {
+            return Err(EnvironmentError(format!(
+                "expected {} pixels for {}x{}, got {}",
+                width * height,
+                width,
+                height,
+                pixels.len()
+            )));
+        }
+
+        // Rows near the poles cover less solid angle, so weight by sin(theta)
+        let weights: Vec<f64> = (0..height)
+            .flat_map(|y| {
+                let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+                pixels[y * width..(y + 1) * width]
+                    .iter()
+                    .map(move |c| luminance(*c).max(0.0) * sin_theta)
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            rotation: rotation.to_radians(),
+            intensity,
+            distribution: Distribution2D::new(&weights, width, height),
+        })
+    }
+
+    fn direction_to_uv(&self, direction: Vec3) -> (f64, f64) {
+        let d = Vec3::unit_vector(&direction);
+        let phi = d.z().atan2(d.x()) + self.rotation;
+        let theta = d.y().clamp(-1.0, 1.0).acos();
+        let u = (phi / (2.0 * PI)).rem_euclid(1.0);
+        (u, theta / PI)
+    }
+
+    fn uv_to_direction(&self, u: f64, v: f64) -> Vec3 {
+        let phi = u * 2.0 * PI - self.rotation;
+        let theta = v * PI;
+        Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+    }
+
+    fn texel(&self, u: f64, v: f64) -> (usize, usize) {
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        (x, y)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: Vec3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let (x, y) = self.texel(u, v);
+        self.pixels[y * self.width + x] * self.intensity
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(Vec3, f64)> {
+        let (r1, r2) = sampler.next_2d();
+        let (u, v, pdf_uv) = self.distribution.sample(r1, r2)?;
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return None;
+        }
+        // Jacobian of the equirectangular mapping
+        let pdf = pdf_uv / (2.0 * PI * PI * sin_theta);
+        Some((self.uv_to_direction(u, v), pdf))
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = self.texel(u, v);
+        self.distribution.pdf(x, y) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[inline]
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+// Piecewise-constant distribution over a grid: a marginal over rows and a conditional per row
+pub struct Distribution2D {
+    width: usize,
+    height: usize,
+    // Normalized cumulative sums, one per row (width + 1 entries each)
+    conditional_cdf: Vec<Vec<f64>>,
+    row_weights: Vec<f64>,
+    marginal_cdf: Vec<f64>,
+    weights: Vec<f64>,
+    total: f64,
+}
+
+impl Distribution2D {
+    pub fn new(weights: &[f64], width: usize, height: usize) -> Self {
+        let mut conditional_cdf = Vec::with_capacity(height);
+        let mut row_weights = Vec::with_capacity(height);
+        for row in weights.chunks(width) {
+            let (cdf, sum) = cumulative(row);
+            conditional_cdf.push(cdf);
+            row_weights.push(sum);
+        }
+        let (marginal_cdf, total) = cumulative(&row_weights);
+        Self {
+            width,
+            height,
+            conditional_cdf,
+            row_weights,
+            marginal_cdf,
+            weights: weights.to_vec(),
+            total,
+        }
+    }
+
+    // Returns (u, v) in [0, 1)^2 and the density with respect to area in uv space
+    pub fn sample(&self, r1: f64, r2: f64) -> Option<(f64, f64, f64)> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let (y, dy) = sample_cdf(&self.marginal_cdf, r1);
+        if self.row_weights[y] <= 0.0 {
+            return None;
+        }
+        let (x, dx) = sample_cdf(&self.conditional_cdf[y], r2);
+        let u = (x as f64 + dx) / self.width as f64;
+        let v = (y as f64 + dy) / self.height as f64;
+        Some((u, v, self.pdf(x, y)))
+    }
+
+    fn pdf(&self, x: usize, y: usize) -> f64 {
+        if self.total <= 0.0 {
+            return 0.0;
+        }
+        self.weights[y * self.width + x] * (self.width * self.height) as f64 / self.total
+    }
+}
+
+fn cumulative(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut cdf = Vec::with_capacity(values.len() + 1);
+    let mut sum = 0.0;
+    cdf.push(0.0);
+    for v in values {
+        sum += v;
+        cdf.push(sum);
+    }
+    if sum > 0.0 {
+        for c in cdf.iter_mut() {
+            *c /= sum;
+        }
+    }
+    (cdf, sum)
+}
+
+// Finds the bucket containing r and how far into it r falls
+fn sample_cdf(cdf: &[f64], r: f64) -> (usize, f64) {
+    let count = cdf.len() - 1;
+    let index = cdf.partition_point(|&c| c <= r).saturating_sub(1).min(count - 1);
+    let width = cdf[index + 1] - cdf[index];
+    let offset = if width > 0.0 { (r - cdf[index]) / width } else { 0.5 };
+    (index, offset.clamp(0.0, 1.0 - f64::EPSILON))
+}
+
+// Lets an importance-sampled environment take part in next-event estimation
+pub struct EnvironmentLight {
+    environment: Arc<dyn Environment>,
+}
+
+impl EnvironmentLight {
+    pub fn new(environment: Arc<dyn Environment>) -> Self {
+        Self { environment }
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _p: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let (wi, pdf) = self.environment.sample(sampler)?;
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            distance: f64::INFINITY,
+            radiance: self.environment.radiance(wi),
+            pdf,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    // Only rays that escape the scene reach the environment
+    fn pdf(&self, _origin: Point3, wi: Vec3, distance: f64) -> f64 {
+        if distance.is_finite() {
+            return 0.0;
+        }
+        self.environment.pdf(wi)
+    }
+}
@@ -1,14 +1,81 @@
+use std::sync::Arc;
+use crate::ray_tracer::ray::Ray;
 use crate::ray_tracer::vec3::Vec3;
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::light::orthonormal_basis;
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub p: Vec3,
+    // Outward normal used for shading, whichever side was hit. Smooth where the primitive
+    // interpolates normals, otherwise the same as `geometric_normal`.
     pub normal: Vec3,
+    // Outward normal of the actual surface
+    pub geometric_normal: Vec3,
+    // Shading frame around `normal`: the tangent follows increasing u where the surface
+    // defines it, and bitangent = normal x tangent
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
     pub t: f64,
+    // Whether the ray arrived from the side the outward normal points to, judged by the
+    // geometric normal
+    pub front_face: bool,
+    // Surface coordinates of the hit point
+    pub u: f64,
+    pub v: f64,
+    pub material: Option<Arc<dyn Material>>,
+    // Index of the hit object in the list the world was built from
+    pub object_id: u32,
 }
 
 impl HitRecord {
     pub fn new() -> Self {
-        HitRecord { p: Vec3::new(0.0, 0.0, 0.0), normal: Vec3::new(0.0, 0.0, 0.0), t: 0.0 }
+        HitRecord {
+            p: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            geometric_normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 0.0),
+            t: 0.0,
+            front_face: true,
+            u: 0.0,
+            v: 0.0,
+            material: None,
+            object_id: 0,
+        }
     }
-}
\ No newline at end of file
+
+    // Sets both normals to the surface's unit outward normal
+    #[inline]
+    pub fn set_outward_normal(&mut self, r: &Ray, outward_normal: Vec3) {
+        self.normal = outward_normal;
+        self.geometric_normal = outward_normal;
+        self.front_face = Vec3::dot(r.direction(), outward_normal) < 0.0;
+    }
+
+    // Replaces the shading normal, leaving the geometric normal and front_face alone
+    #[inline]
+    pub fn set_shading_normal(&mut self, normal: Vec3) {
+        self.normal = normal;
+    }
+
+    // Builds the tangent frame around the shading normal, with the tangent as close to
+    // `direction` as it can be. Any frame will do where the direction is undefined, such as
+    // the poles of a sphere.
+    #[inline]
+    pub fn set_tangent(&mut self, direction: Vec3) {
+        let projected = direction - self.normal * Vec3::dot(direction, self.normal);
+        if projected.length_squared() <= 1e-16 * direction.length_squared() {
+            (self.tangent, self.bitangent) = orthonormal_basis(self.normal);
+        } else {
+            self.tangent = Vec3::unit_vector(&projected);
+            self.bitangent = Vec3::cross(self.normal, self.tangent);
+        }
+    }
+}
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,9 @@
 use crate::ray_tracer::ray::Ray;
 use crate::ray_tracer::hit_record::HitRecord;
 use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::aabb::Aabb;
 
 pub trait Hittable: Send + Sync {
     fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool;
+    fn bounding_box(&self) -> Aabb;
 }
\ No newline at end of file
@@ -1,28 +1,62 @@
 use std::sync::Arc;
 use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::aabb::Aabb;
 
+// Objects only go in through `add`, which keeps the cached bounding box up to date
 pub struct HittableList {
-    pub objects: Vec<Arc<dyn Hittable>>,
+    objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
 }
 
 impl HittableList {
     pub fn new() -> Self {
-        HittableList { objects: Vec::new() }
+        HittableList { objects: Vec::new(), bbox: Aabb::EMPTY }
     }
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
         self.objects.push(object);
     }
-    pub fn hit(&self, r: &crate::ray_tracer::ray::Ray, t_range: crate::ray_tracer::interval::Interval, rec: &mut crate::ray_tracer::hit_record::HitRecord) -> bool {
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    pub fn into_objects(self) -> Vec<Arc<dyn Hittable>> {
+        self.objects
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, r: &crate::ray_tracer::ray::Ray, t_range: crate::ray_tracer::interval::Interval, rec: &mut crate::ray_tracer::hit_record::HitRecord) -> bool {
         let mut temp_rec = crate::ray_tracer::hit_record::HitRecord::new();
         let mut hit_anything = false;
         let mut closest_so_far = t_range.max;
-        for object in &self.objects {
+        for (i, object) in self.objects.iter().enumerate() {
             if object.hit(r, crate::ray_tracer::interval::Interval::new(t_range.min, closest_so_far), &mut temp_rec) {
                 hit_anything = true;
                 closest_so_far = temp_rec.t;
+                temp_rec.object_id = i as u32;
                 *rec = temp_rec.clone();
             }
         }
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use exr::prelude::{AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, SmallVec, WritableImage};
+
+use crate::ray_tracer::aov::Aov;
+
+pub fn is_supported(path: &Path) -> bool {
+    is_hdr(path) || matches!(lowercase_extension(path).as_deref(), Some("png") | Some("ppm"))
+}
+
+// Formats that take linear float passes through `write_aovs` instead of display pixels
+pub fn is_hdr(path: &Path) -> bool {
+    matches!(lowercase_extension(path).as_deref(), Some("exr") | Some("pfm"))
+}
+
+fn lowercase_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+// Writes an RGBA8 buffer (as produced by `Renderer::render_rgba`) to disk.
+// The format is picked from the file extension: `.png` or `.ppm`.
+pub fn write_rgba(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    match lowercase_extension(path).as_deref() {
+        Some("png") => write_png(path, width, height, rgba),
+        Some("ppm") => write_ppm(path, width, height, rgba),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported image format for '{}' (expected .png or .ppm)", path.display()),
+        )),
+    }
+}
+
+pub fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    image::save_buffer(path, rgba, width, height, image::ExtendedColorType::Rgba8)
+        .map_err(io::Error::other)
+}
+
+// Binary PPM (P6); alpha is dropped
+pub fn write_ppm(path: &Path, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    for pixel in rgba.chunks_exact(4) {
+        out.write_all(&pixel[..3])?;
+    }
+    out.flush()
+}
+
+// Writes linear passes to disk; the first one is the main image. An `.exr` file holds all
+// of them as layers of one image (the first as plain R, G, B, the rest as `name.channel`).
+// With `.pfm`, which stores a single image, the first pass goes to `path` and every other
+// one to a `<stem>.<name>.pfm` file next to it. Returns the files written.
+pub fn write_aovs(path: &Path, width: u32, height: u32, aovs: &[Aov]) -> io::Result<Vec<PathBuf>> {
+    match lowercase_extension(path).as_deref() {
+        Some("exr") => {
+            write_exr(path, width, height, aovs)?;
+            Ok(vec![path.to_path_buf()])
+        }
+        Some("pfm") => {
+            let mut written = Vec::new();
+            for (i, aov) in aovs.iter().enumerate() {
+                let file = if i == 0 { path.to_path_buf() } else { sibling_path(path, aov.name) };
+                write_pfm(&file, width, height, aov)?;
+                written.push(file);
+            }
+            Ok(written)
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unsupported HDR format for '{}' (expected .exr or .pfm)", path.display()),
+        )),
+    }
+}
+
+// `out/render.pfm` with name `depth` becomes `out/render.depth.pfm`
+fn sibling_path(path: &Path, name: &str) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("render");
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("pfm");
+    path.with_file_name(format!("{}.{}.{}", stem, name, extension))
+}
+
+pub fn write_exr(path: &Path, width: u32, height: u32, aovs: &[Aov]) -> io::Result<()> {
+    let mut channels = SmallVec::new();
+    for (i, aov) in aovs.iter().enumerate() {
+        for (c, channel) in aov.channels.iter().enumerate() {
+            let name = if i == 0 { channel.to_string() } else { format!("{}.{}", aov.name, channel) };
+            channels.push(AnyChannel::new(name.as_str(), FlatSamples::F32(aov.channel(c))));
+        }
+    }
+
+    let layer = Layer::new(
+        (width as usize, height as usize),
+        LayerAttributes::named("render"),
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels),
+    );
+    Image::from_layer(layer)
+        .write()
+        .to_file(path)
+        .map_err(io::Error::other)
+}
+
+// Portable float map: `PF` for three channels, `Pf` for one, little endian, bottom row first
+pub fn write_pfm(path: &Path, width: u32, height: u32, aov: &Aov) -> io::Result<()> {
+    let channels = aov.channels.len();
+    let magic = match channels {
+        1 => "Pf",
+        3 => "PF",
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("PFM can't store the {} channels of '{}'", channels, aov.name),
+            ))
+        }
+    };
+
+    let mut out = BufWriter::new(File::create(path)?);
+    write!(out, "{}\n{} {}\n-1.0\n", magic, width, height)?;
+    let row_len = width as usize * channels;
+    for row in aov.data.chunks_exact(row_len).rev() {
+        for value in row {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.flush()
+}
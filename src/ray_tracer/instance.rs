@@ -0,0 +1,70 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::matrix::Matrix4;
+
+// Places a shared object in the world through an affine transform. Many instances can
+// point at the same geometry, so a mesh placed a thousand times is stored once.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    to_world: Matrix4,
+    to_object: Matrix4,
+    bbox: Aabb,
+}
+
+impl Instance {
+    // None when the transform is singular, e.g. scaled by zero along an axis
+    pub fn new(object: Arc<dyn Hittable>, to_world: Matrix4) -> Option<Self> {
+        let to_object = to_world.inverse()?;
+
+        // The eight corners of the object's box, moved into the world
+        let b = object.bounding_box();
+        let mut bbox = Aabb::EMPTY;
+        for x in [b.x.min, b.x.max] {
+            for y in [b.y.min, b.y.max] {
+                for z in [b.z.min, b.z.max] {
+                    let corner = to_world.transform_point(Point3::new(x, y, z));
+                    bbox = Aabb::surrounding(&bbox, &Aabb::from_points(corner, corner));
+                }
+            }
+        }
+
+        Some(Self { object, to_world, to_object, bbox })
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> Matrix4 {
+        self.to_world
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        // The direction is not renormalized, so t means the same in both spaces
+        let local = Ray::new(self.to_object.transform_point(r.origin()), self.to_object.transform_vector(r.direction()));
+        if !self.object.hit(&local, t_range, rec) {
+            return false;
+        }
+
+        // The inverse transpose keeps normals on the same side of the ray, so front_face
+        // carries over unchanged
+        rec.p = self.to_world.transform_point(rec.p);
+        rec.normal = Vec3::unit_vector(&self.to_object.transform_normal(rec.normal));
+        rec.geometric_normal = Vec3::unit_vector(&self.to_object.transform_normal(rec.geometric_normal));
+        // Tangents are directions on the surface and move like any other vector
+        let tangent = self.to_world.transform_vector(rec.tangent);
+        rec.set_tangent(tangent);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
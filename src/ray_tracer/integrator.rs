@@ -0,0 +1,286 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::ray_tracer::vec3::{Vec3, Color};
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::light::LightList;
+use crate::ray_tracer::environment::Environment;
+use crate::ray_tracer::material::{Lobe, Material};
+use crate::ray_tracer::pixel_data::PixelData;
+use crate::ray_tracer::sampler::Sampler;
+
+// Everything an integrator can query while tracing
+pub struct SceneView<'a> {
+    pub world: &'a dyn Hittable,
+    pub lights: &'a LightList,
+    pub environment: &'a dyn Environment,
+}
+
+// Counters an integrator keeps across all the samples it traced
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PathStatistics {
+    pub paths: u64,
+    // Surface hits that scattered, summed over all paths
+    pub bounces: u64,
+    pub ended_by_roulette: u64,
+    pub ended_by_depth: u64,
+}
+
+impl PathStatistics {
+    pub fn average_path_length(&self) -> f64 {
+        if self.paths == 0 { 0.0 } else { self.bounces as f64 / self.paths as f64 }
+    }
+}
+
+impl fmt::Display for PathStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |n: u64| if self.paths == 0 { 0.0 } else { 100.0 * n as f64 / self.paths as f64 };
+        write!(
+            f,
+            "{:.2} bounces per path ({:.1}% ended by Russian roulette, {:.1}% by depth limits)",
+            self.average_path_length(),
+            percent(self.ended_by_roulette),
+            percent(self.ended_by_depth)
+        )
+    }
+}
+
+// Computes the radiance arriving along a camera ray, plus the G-buffer data of the first hit.
+// The renderer owns accumulation, so an integrator only ever traces a single sample.
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, r: &Ray, scene: &SceneView, sampler: &mut dyn Sampler) -> (Color, PixelData);
+
+    fn statistics(&self) -> Option<PathStatistics> {
+        None
+    }
+
+    fn reset_statistics(&self) {}
+}
+
+// How long paths may get. A path ends when it exceeds the total depth or the limit for
+// the kind of bounce it just made.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathSettings {
+    pub max_depth: u32,
+    pub max_diffuse: u32,
+    pub max_specular: u32,
+    pub max_transmission: u32,
+    // Bounces before Russian roulette may end a path; None disables it
+    pub roulette_depth: Option<u32>,
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        Self { max_depth: 10, max_diffuse: 10, max_specular: 10, max_transmission: 10, roulette_depth: Some(3) }
+    }
+}
+
+// Unidirectional path tracer. Non-specular hits sample one light directly (next-event
+// estimation) and combine it with BSDF sampling through MIS. Past the roulette depth,
+// paths carrying little energy are ended at random and survivors are weighted up, which
+// saves time without adding bias.
+pub struct PathIntegrator {
+    settings: PathSettings,
+    paths: AtomicU64,
+    bounces: AtomicU64,
+    ended_by_roulette: AtomicU64,
+    ended_by_depth: AtomicU64,
+}
+
+impl PathIntegrator {
+    pub fn new(settings: PathSettings) -> Self {
+        Self {
+            settings,
+            paths: AtomicU64::new(0),
+            bounces: AtomicU64::new(0),
+            ended_by_roulette: AtomicU64::new(0),
+            ended_by_depth: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn radiance(&self, r: &Ray, scene: &SceneView, sampler: &mut dyn Sampler) -> (Color, PixelData) {
+        let (color, pixel_data, path) = ray_color_iterative_with_data(r, scene, &self.settings, sampler);
+        self.paths.fetch_add(1, Ordering::Relaxed);
+        self.bounces.fetch_add(u64::from(path.bounces), Ordering::Relaxed);
+        match path.end {
+            PathEnd::Roulette => self.ended_by_roulette.fetch_add(1, Ordering::Relaxed),
+            PathEnd::DepthLimit => self.ended_by_depth.fetch_add(1, Ordering::Relaxed),
+            PathEnd::Terminated => 0,
+        };
+        (color, pixel_data)
+    }
+
+    fn statistics(&self) -> Option<PathStatistics> {
+        Some(PathStatistics {
+            paths: self.paths.load(Ordering::Relaxed),
+            bounces: self.bounces.load(Ordering::Relaxed),
+            ended_by_roulette: self.ended_by_roulette.load(Ordering::Relaxed),
+            ended_by_depth: self.ended_by_depth.load(Ordering::Relaxed),
+        })
+    }
+
+    fn reset_statistics(&self) {
+        for counter in [&self.paths, &self.bounces, &self.ended_by_roulette, &self.ended_by_depth] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+// Why a path stopped
+enum PathEnd {
+    // Escaped to the environment or absorbed
+    Terminated,
+    Roulette,
+    DepthLimit,
+}
+
+struct PathInfo {
+    bounces: u32,
+    end: PathEnd,
+}
+
+// Balances two sampling strategies; the arguments are the densities of each for the same direction
+#[inline]
+fn power_heuristic(pdf_a: f64, pdf_b: f64) -> f64 {
+    let a = pdf_a * pdf_a;
+    let b = pdf_b * pdf_b;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
+
+// Direct lighting at a hit by sampling one light and tracing a shadow ray
+fn sample_direct_light(r_in: &Ray, rec: &HitRecord, material: &dyn Material, world: &dyn Hittable, lights: &LightList, sampler: &mut dyn Sampler) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+    let Some((sample, is_delta)) = lights.sample(rec.p, sampler) else {
+        return black;
+    };
+    if sample.pdf <= 0.0 {
+        return black;
+    }
+
+    let f = material.scattering_value(r_in, rec, sample.wi);
+    if f.near_zero() {
+        return black;
+    }
+
+    // Stop just short of the light so its own surface doesn't count as an occluder
+    let shadow_ray = Ray::new(rec.p, sample.wi);
+    let max_t = sample.distance * (1.0 - 1e-4) - 0.001;
+    let mut shadow_rec = HitRecord::new();
+    if world.hit(&shadow_ray, Interval::new(0.001, max_t), &mut shadow_rec) {
+        return black;
+    }
+
+    let weight = if is_delta {
+        1.0
+    } else {
+        power_heuristic(sample.pdf, material.scattering_pdf(r_in, rec, sample.wi))
+    };
+    f * sample.radiance * (weight / sample.pdf)
+}
+
+fn ray_color_iterative_with_data(r: &Ray, scene: &SceneView, settings: &PathSettings, sampler: &mut dyn Sampler) -> (Color, PixelData, PathInfo) {
+    let SceneView { world, lights, environment } = *scene;
+    let mut current_ray = *r;
+    let mut attenuation = Color::new(1.0, 1.0, 1.0);
+    let mut radiance = Color::new(0.0, 0.0, 0.0);
+    let mut pixel_data = PixelData::new();
+    let mut first_hit = true;
+
+    // Density of the BSDF sample that produced current_ray, or None when emission
+    // it finds must be counted in full (camera rays and specular bounces)
+    let mut bsdf_pdf: Option<f64> = None;
+
+    let mut bounces = 0;
+    let (mut diffuse, mut specular, mut transmission) = (0, 0, 0);
+    let end = loop {
+        let mut rec = HitRecord::new();
+        if !world.hit(&current_ray, Interval::new(0.001, f64::INFINITY), &mut rec) {
+            // Escaped to the environment
+            let background = environment.radiance(current_ray.direction());
+            let weight = match bsdf_pdf {
+                Some(pdf) => {
+                    let direction = Vec3::unit_vector(&current_ray.direction());
+                    power_heuristic(pdf, lights.pdf(current_ray.origin(), direction, f64::INFINITY))
+                }
+                None => 1.0,
+            };
+            radiance += attenuation * background * weight;
+            break PathEnd::Terminated;
+        }
+
+        let mut next_bsdf_pdf = None;
+        let scattered = match &rec.material {
+            Some(material) => {
+                let emitted = material.emitted(&rec);
+                if !emitted.near_zero() {
+                    let weight = match bsdf_pdf {
+                        Some(pdf) => {
+                            let direction = current_ray.direction();
+                            let light_pdf = lights.pdf(current_ray.origin(), Vec3::unit_vector(&direction), rec.t * direction.length());
+                            power_heuristic(pdf, light_pdf)
+                        }
+                        None => 1.0,
+                    };
+                    radiance += attenuation * emitted * weight;
+                }
+
+                let scattered = material.sample(&current_ray, &rec, sampler);
+                if let Some(bsdf) = &scattered {
+                    if !material.is_specular() && !lights.is_empty() {
+                        radiance += attenuation * sample_direct_light(&current_ray, &rec, material.as_ref(), world, lights, sampler);
+                        if !bsdf.is_specular {
+                            next_bsdf_pdf = Some(bsdf.pdf);
+                        }
+                    }
+                }
+                scattered
+            }
+            None => None,
+        };
+
+        // Store G-buffer data from first hit only
+        if first_hit {
+            pixel_data.depth = rec.t as f32;
+            pixel_data.normal = rec.normal;
+            pixel_data.albedo = rec.material.as_ref().map_or(Color::new(0.0, 0.0, 0.0), |m| m.albedo(&current_ray, &rec));
+            first_hit = false;
+        }
+
+        let Some(bsdf) = scattered else {
+            // Absorbed by the surface (or a light source)
+            break PathEnd::Terminated;
+        };
+
+        bounces += 1;
+        let (count, limit) = match bsdf.lobe {
+            Lobe::Diffuse => (&mut diffuse, settings.max_diffuse),
+            Lobe::Specular => (&mut specular, settings.max_specular),
+            Lobe::Transmission => (&mut transmission, settings.max_transmission),
+        };
+        *count += 1;
+        if bounces > settings.max_depth || *count > limit {
+            break PathEnd::DepthLimit;
+        }
+
+        current_ray = Ray::new(rec.p, bsdf.wi);
+        attenuation = attenuation * bsdf.weight();
+        bsdf_pdf = next_bsdf_pdf;
+
+        // Survive with a probability that follows the path's throughput
+        if settings.roulette_depth.is_some_and(|depth| bounces >= depth) {
+            let survival = attenuation.x().max(attenuation.y()).max(attenuation.z()).min(0.95);
+            if survival <= 0.0 || sampler.next_1d() >= survival {
+                break PathEnd::Roulette;
+            }
+            attenuation = attenuation / survival;
+        }
+    };
+
+    pixel_data.color = radiance;
+    (radiance, pixel_data, PathInfo { bounces, end })
+}
@@ -17,6 +17,14 @@ impl Interval {
         Self { min, max }
     }
 
+    // Create the tightest interval enclosing both input intervals
+    pub fn enclosing(a: Interval, b: Interval) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
     // Returns the size of the interval
     pub fn size(&self) -> f64 {
         self.max - self.min
@@ -43,6 +51,12 @@ impl Interval {
         }
     }
 
+    // Pad the interval by delta, split evenly on both sides
+    pub fn expand(&self, delta: f64) -> Interval {
+        let padding = delta / 2.0;
+        Interval::new(self.min - padding, self.max + padding)
+    }
+
     // Predefined empty and universe intervals
     pub const EMPTY: Interval = Interval {
         min: f64::INFINITY,
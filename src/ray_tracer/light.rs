@@ -0,0 +1,361 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::material::DiffuseLight;
+use crate::ray_tracer::quad::Quad;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::sampler::Sampler;
+use crate::ray_tracer::sphere::Sphere;
+use crate::ray_tracer::vec3::{Color, Point3, Vec3};
+
+// A direction towards a light chosen for a shading point
+pub struct LightSample {
+    // Unit direction from the shading point towards the light
+    pub wi: Vec3,
+    // Distance to the sampled point, infinite for directional lights
+    pub distance: f64,
+    // Radiance arriving along wi (for delta lights, the incident intensity)
+    pub radiance: Color,
+    // Solid angle density of wi; 1 for delta lights
+    pub pdf: f64,
+}
+
+pub trait Light: Send + Sync {
+    fn sample(&self, p: Point3, sampler: &mut dyn Sampler) -> Option<LightSample>;
+
+    // Point, spot and directional lights can't be hit by rays, so they are never MIS weighted
+    fn is_delta(&self) -> bool;
+
+    // Solid angle density with which `sample` would pick unit direction `wi` from `origin`,
+    // provided the light's surface is the first thing hit at `distance`
+    fn pdf(&self, _origin: Point3, _wi: Vec3, _distance: f64) -> f64 {
+        0.0
+    }
+
+    // Geometry that makes an area light visible to camera and bounce rays
+    fn geometry(&self) -> Option<Arc<dyn Hittable>> {
+        None
+    }
+}
+
+pub struct PointLight {
+    position: Point3,
+    intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> Self {
+        Self { position, intensity }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            wi: to_light / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+// A point light restricted to a cone, fading out between the inner and outer angle
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    // Angles are half-angles of the cone in degrees
+    pub fn new(position: Point3, direction: Vec3, intensity: Color, inner_angle: f64, outer_angle: f64) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        Self {
+            position,
+            direction: Vec3::unit_vector(&direction),
+            intensity,
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        // Smoothstep between the two cones
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_light = self.position - p;
+        let distance_squared = to_light.length_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let wi = to_light / distance;
+        let falloff = self.falloff(Vec3::dot(-wi, self.direction));
+        if falloff == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            distance,
+            radiance: self.intensity * (falloff / distance_squared),
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+// Parallel light from infinitely far away, like the sun
+pub struct DirectionalLight {
+    // Direction the light travels in
+    direction: Vec3,
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Color) -> Self {
+        Self { direction: Vec3::unit_vector(&direction), irradiance }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: Point3, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        Some(LightSample {
+            wi: -self.direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+            pdf: 1.0,
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+}
+
+// Emissive sphere, sampled uniformly over the cone it subtends
+pub struct SphereLight {
+    center: Point3,
+    radius: f64,
+    emission: Color,
+    shape: Arc<dyn Hittable>,
+}
+
+impl SphereLight {
+    pub fn new(center: Point3, radius: f64, emission: Color) -> Self {
+        let shape: Arc<dyn Hittable> = Arc::new(Sphere::new(center, radius, Arc::new(DiffuseLight::new(emission))));
+        Self { center, radius, emission, shape }
+    }
+
+    fn cos_theta_max(&self, p: Point3) -> Option<f64> {
+        let distance_squared = (self.center - p).length_squared();
+        let radius_squared = self.radius * self.radius;
+        // Points inside the light can't see it as a cone
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        Some((1.0 - radius_squared / distance_squared).sqrt())
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, p: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let cos_theta_max = self.cos_theta_max(p)?;
+
+        // Uniform direction inside the cone around the axis towards the center
+        let axis = Vec3::unit_vector(&(self.center - p));
+        let (r1, r2) = sampler.next_2d();
+        let cos_theta = 1.0 - r1 * (1.0 - cos_theta_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * r2;
+        let (tangent, bitangent) = orthonormal_basis(axis);
+        let wi = tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta;
+
+        let mut rec = HitRecord::new();
+        let distance = if self.shape.hit(&Ray::new(p, wi), Interval::new(0.0, f64::INFINITY), &mut rec) {
+            rec.t
+        } else {
+            // Grazing the silhouette; use the tangent distance
+            (self.center - p).length() * cos_theta_max
+        };
+
+        Some(LightSample {
+            wi,
+            distance,
+            radiance: self.emission,
+            pdf: 1.0 / (2.0 * PI * (1.0 - cos_theta_max)),
+        })
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn pdf(&self, origin: Point3, wi: Vec3, distance: f64) -> f64 {
+        if !hits_at(self.shape.as_ref(), origin, wi, distance) {
+            return 0.0;
+        }
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => 1.0 / (2.0 * PI * (1.0 - cos_theta_max)),
+            None => 0.0,
+        }
+    }
+
+    fn geometry(&self) -> Option<Arc<dyn Hittable>> {
+        Some(Arc::clone(&self.shape))
+    }
+}
+
+// Emissive parallelogram, sampled uniformly by area. Both sides emit.
+pub struct QuadLight {
+    quad: Arc<Quad>,
+    emission: Color,
+}
+
+impl QuadLight {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, emission: Color) -> Self {
+        let quad = Arc::new(Quad::new(corner, u, v, Arc::new(DiffuseLight::new(emission))));
+        Self { quad, emission }
+    }
+
+    // Converts the area density 1/A at distance d to a solid angle density
+    fn solid_angle_pdf(&self, wi: Vec3, distance: f64) -> f64 {
+        let cosine = Vec3::dot(self.quad.normal(), wi).abs();
+        if cosine < 1e-8 {
+            return 0.0;
+        }
+        distance * distance / (cosine * self.quad.area())
+    }
+}
+
+impl Light for QuadLight {
+    fn sample(&self, p: Point3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let (u, v) = self.quad.edges();
+        let (s, t) = sampler.next_2d();
+        let point = self.quad.corner() + u * s + v * t;
+        let to_light = point - p;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+        let wi = to_light / distance;
+        let pdf = self.solid_angle_pdf(wi, distance);
+        if pdf == 0.0 {
+            return None;
+        }
+        Some(LightSample { wi, distance, radiance: self.emission, pdf })
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn pdf(&self, origin: Point3, wi: Vec3, distance: f64) -> f64 {
+        if !hits_at(self.quad.as_ref(), origin, wi, distance) {
+            return 0.0;
+        }
+        self.solid_angle_pdf(wi, distance)
+    }
+
+    fn geometry(&self) -> Option<Arc<dyn Hittable>> {
+        Some(self.quad.clone())
+    }
+}
+
+// True if the shape is hit along the ray at (nearly) the given distance
+fn hits_at(shape: &dyn Hittable, origin: Point3, wi: Vec3, distance: f64) -> bool {
+    // Escaped rays can only have reached the environment
+    if !distance.is_finite() {
+        return false;
+    }
+    let tolerance = 1e-4 * distance.max(1.0);
+    let mut rec = HitRecord::new();
+    shape.hit(
+        &Ray::new(origin, wi),
+        Interval::new(distance - tolerance, distance + tolerance),
+        &mut rec,
+    )
+}
+
+// Any two unit vectors perpendicular to n and to each other
+pub fn orthonormal_basis(n: Vec3) -> (Vec3, Vec3) {
+    let helper = if n.x().abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = Vec3::unit_vector(&Vec3::cross(helper, n));
+    let bitangent = Vec3::cross(n, tangent);
+    (tangent, bitangent)
+}
+
+// The lights of a scene, picked uniformly for next-event estimation
+#[derive(Default)]
+pub struct LightList {
+    lights: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self { lights: Vec::new() }
+    }
+
+    pub fn add(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+
+    // Picks one light and samples it; the returned pdf includes the selection probability
+    pub fn sample(&self, p: Point3, sampler: &mut dyn Sampler) -> Option<(LightSample, bool)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count = self.lights.len();
+        let index = ((sampler.next_1d() * count as f64) as usize).min(count - 1);
+        let light = &self.lights[index];
+        let mut sample = light.sample(p, sampler)?;
+        sample.pdf /= count as f64;
+        Some((sample, light.is_delta()))
+    }
+
+    // Density with which `sample` picks direction wi, given the emitter hit at `distance`
+    pub fn pdf(&self, origin: Point3, wi: Vec3, distance: f64) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.lights.iter().map(|light| light.pdf(origin, wi, distance)).sum();
+        total / self.lights.len() as f64
+    }
+}
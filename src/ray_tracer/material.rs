@@ -0,0 +1,434 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::environment::luminance;
+use crate::ray_tracer::microfacet::{directional_albedo, fresnel_schlick, ggx_d, sample_vndf, smith_g2, vndf_reflection_pdf, MIN_ALPHA};
+use crate::ray_tracer::sampler::Sampler;
+use crate::ray_tracer::texture::{ConstantTexture, Texture};
+use crate::ray_tracer::vec3::{Vec3, Color};
+
+// A scattered direction picked by a material, with what the path tracer needs to weight it
+pub struct BsdfSample {
+    // Unit direction the path continues in
+    pub wi: Vec3,
+    // BSDF times the cosine term for wi
+    pub value: Color,
+    // Solid angle density of wi. Specular samples use 1, making `value` the path weight.
+    pub pdf: f64,
+    pub is_specular: bool,
+    pub lobe: Lobe,
+}
+
+// Kind of bounce a sample makes, each with its own depth limit in the path tracer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lobe {
+    Diffuse,
+    // Mirror-like and glossy reflection
+    Specular,
+    // Refraction into or out of a surface
+    Transmission,
+}
+
+impl BsdfSample {
+    // Factor the path throughput is multiplied by
+    pub fn weight(&self) -> Color {
+        self.value / self.pdf
+    }
+}
+
+pub trait Material: Send + Sync {
+    // Picks the direction the path continues in, or None when the path is absorbed
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample>;
+
+    // Light given off by the surface itself, black for anything that is not a light
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // What the weights of `sample` average to for a ray arriving along `r_in`, free of the
+    // noise of any one sample. The denoiser keeps it as the surface color of the pixel.
+    fn albedo(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Materials that can't be evaluated for an arbitrary direction (mirrors, glass,
+    // fuzzy metal) are skipped by light sampling and only see lights through `sample`
+    fn is_specular(&self) -> bool {
+        true
+    }
+
+    // BSDF times the cosine term for light arriving along unit direction `wi`
+    fn scattering_value(&self, _r_in: &Ray, _rec: &HitRecord, _wi: Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    // Solid angle density with which `sample` picks the unit direction `wi`
+    fn scattering_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _wi: Vec3) -> f64 {
+        0.0
+    }
+}
+
+// Primitives report the outward normal; opaque surfaces shade whichever side the ray arrived from
+#[inline]
+fn facing_normal(rec: &HitRecord) -> Vec3 {
+    if rec.front_face { rec.normal } else { -rec.normal }
+}
+
+// The hit's tangent frame around `facing_normal`, as (tangent, bitangent, normal). Turning
+// the normal over flips the bitangent too, so the frame stays right-handed.
+#[inline]
+fn shading_frame(rec: &HitRecord) -> (Vec3, Vec3, Vec3) {
+    if rec.front_face {
+        (rec.tangent, rec.bitangent, rec.normal)
+    } else {
+        (rec.tangent, -rec.bitangent, -rec.normal)
+    }
+}
+
+// Cosine-distributed direction around +z (Malley's method: uniform disk projected up)
+pub fn cosine_hemisphere((u1, u2): (f64, f64)) -> Vec3 {
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - u1).max(0.0).sqrt())
+}
+
+pub struct Lambertian {
+    albedo: Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Self::textured(Arc::new(ConstantTexture::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Lambertian {
+    fn sample(&self, _r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let (tangent, bitangent, normal) = shading_frame(rec);
+        let local = cosine_hemisphere(sampler.next_2d());
+        let wi = tangent * local.x() + bitangent * local.y() + normal * local.z();
+
+        let pdf = local.z() / PI;
+        if pdf <= 0.0 {
+            return None;
+        }
+        let albedo = self.albedo.value(rec.u, rec.v, rec.p);
+        Some(BsdfSample { wi, value: albedo * pdf, pdf, is_specular: false, lobe: Lobe::Diffuse })
+    }
+
+    fn albedo(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn scattering_value(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let cosine = Vec3::dot(facing_normal(rec), wi).max(0.0);
+        self.albedo.value(rec.u, rec.v, rec.p) * (cosine / PI)
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        Vec3::dot(facing_normal(rec), wi).max(0.0) / PI
+    }
+}
+
+pub struct Metal {
+    albedo: Arc<dyn Texture>,
+    // Read from the first channel and clamped to [0, 1]
+    fuzz: Arc<dyn Texture>,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f64) -> Self {
+        let fuzz = fuzz.clamp(0.0, 1.0);
+        Self::textured(
+            Arc::new(ConstantTexture::new(albedo)),
+            Arc::new(ConstantTexture::new(Color::new(fuzz, fuzz, fuzz))),
+        )
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: Arc<dyn Texture>) -> Self {
+        Self { albedo, fuzz }
+    }
+}
+
+impl Material for Metal {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let normal = facing_normal(rec);
+        let reflected = Vec3::reflect(Vec3::unit_vector(&r_in.direction()), normal);
+        let fuzz = self.fuzz.value(rec.u, rec.v, rec.p).x().clamp(0.0, 1.0);
+        let wi = Vec3::unit_vector(&(reflected + sampler.random_in_unit_sphere() * fuzz));
+
+        // Fuzzed rays that end up below the surface are absorbed
+        if Vec3::dot(wi, normal) <= 0.0 {
+            return None;
+        }
+        let albedo = self.albedo.value(rec.u, rec.v, rec.p);
+        Some(BsdfSample { wi, value: albedo, pdf: 1.0, is_specular: true, lobe: Lobe::Specular })
+    }
+
+    fn albedo(&self, _r_in: &Ray, rec: &HitRecord) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.p)
+    }
+}
+
+pub struct Dielectric {
+    refraction_index: f64,
+}
+
+impl Dielectric {
+    pub fn new(refraction_index: f64) -> Self {
+        Self { refraction_index }
+    }
+
+    // Schlick's approximation for reflectance
+    fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
+        let r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
+        let r0 = r0 * r0;
+        r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+    }
+}
+
+impl Material for Dielectric {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let unit_direction = Vec3::unit_vector(&r_in.direction());
+
+        let (normal, ri) = if rec.front_face {
+            (rec.normal, 1.0 / self.refraction_index)
+        } else {
+            (-rec.normal, self.refraction_index)
+        };
+
+        let cos_theta = Vec3::dot(-unit_direction, normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        // Total internal reflection or Fresnel reflection
+        let cannot_refract = ri * sin_theta > 1.0;
+        let (direction, lobe) = if cannot_refract || Self::reflectance(cos_theta, ri) > sampler.next_1d() {
+            (Vec3::reflect(unit_direction, normal), Lobe::Specular)
+        } else {
+            (Vec3::refract(unit_direction, normal, ri), Lobe::Transmission)
+        };
+
+        Some(BsdfSample {
+            wi: Vec3::unit_vector(&direction),
+            value: Color::new(1.0, 1.0, 1.0),
+            pdf: 1.0,
+            is_specular: true,
+            lobe,
+        })
+    }
+
+    // Everything is either reflected or refracted
+    fn albedo(&self, _r_in: &Ray, _rec: &HitRecord) -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+}
+
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+    intensity: f64,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        Self::textured(Arc::new(ConstantTexture::new(emit)), 1.0)
+    }
+
+    pub fn textured(emit: Arc<dyn Texture>, intensity: f64) -> Self {
+        Self { emit, intensity }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn sample(&self, _r_in: &Ray, _rec: &HitRecord, _sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        self.emit.value(rec.u, rec.v, rec.p) * self.intensity
+    }
+}
+
+// The glTF metallic-roughness model: a GGX specular lobe over a Lambertian base. Metals
+// tint their reflection with the base color and have no base; everything else reflects
+// 4% at normal incidence (an index of 1.5). The light single-scattering GGX misses at
+// high roughness is added back by scaling the specular lobe (Turquin 2019), and the base
+// gets only what the specular lobe leaves, so a white surface reflects everything.
+pub struct MetallicRoughness {
+    base_color: Arc<dyn Texture>,
+    metallic: f64,
+    roughness: f64,
+    // Packed as in glTF: roughness in green and metallic in blue, each scaled by the factors
+    metallic_roughness: Option<Arc<dyn Texture>>,
+}
+
+// Parameters looked up at one hit
+struct SurfaceParams {
+    base_color: Color,
+    metallic: f64,
+    alpha: f64,
+    // Single-scattering albedo terms for the outgoing direction
+    albedo: (f64, f64),
+}
+
+const DIELECTRIC_F0: f64 = 0.04;
+
+impl MetallicRoughness {
+    pub fn new(base_color: Color, metallic: f64, roughness: f64) -> Self {
+        Self::textured(Arc::new(ConstantTexture::new(base_color)), metallic, roughness, None)
+    }
+
+    pub fn textured(
+        base_color: Arc<dyn Texture>,
+        metallic: f64,
+        roughness: f64,
+        metallic_roughness: Option<Arc<dyn Texture>>,
+    ) -> Self {
+        Self { base_color, metallic: metallic.clamp(0.0, 1.0), roughness: roughness.clamp(0.0, 1.0), metallic_roughness }
+    }
+
+    fn params(&self, rec: &HitRecord, cos_o: f64) -> SurfaceParams {
+        let (mut metallic, mut roughness) = (self.metallic, self.roughness);
+        if let Some(texture) = &self.metallic_roughness {
+            let packed = texture.value(rec.u, rec.v, rec.p);
+            roughness *= packed.y().clamp(0.0, 1.0);
+            metallic *= packed.z().clamp(0.0, 1.0);
+        }
+        let alpha = (roughness * roughness).max(MIN_ALPHA);
+        SurfaceParams {
+            base_color: self.base_color.value(rec.u, rec.v, rec.p),
+            metallic,
+            alpha,
+            albedo: directional_albedo(cos_o, alpha),
+        }
+    }
+
+    fn to_local((t, b, n): (Vec3, Vec3, Vec3), w: Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(w, t), Vec3::dot(w, b), Vec3::dot(w, n))
+    }
+
+    // Multiple-scattering compensation factor, per channel, for a lobe with reflectance f0
+    fn compensation(f0: Color, (e, _): (f64, f64)) -> Color {
+        Color::new(1.0, 1.0, 1.0) + f0 * ((1.0 - e) / e.max(1e-6))
+    }
+
+    // Fraction of light the compensated specular lobe with reflectance f0 reflects
+    fn specular_albedo(f0: Color, albedo: (f64, f64)) -> Color {
+        let (e, s) = albedo;
+        let white = Color::new(1.0, 1.0, 1.0);
+        (f0 * e + (white - f0) * s) * Self::compensation(f0, albedo)
+    }
+
+    // Specular and base albedos for the outgoing direction
+    fn lobe_albedos(p: &SurfaceParams) -> (Color, Color) {
+        let dielectric_f0 = Color::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0);
+        let dielectric = Self::specular_albedo(dielectric_f0, p.albedo);
+        let metal = Self::specular_albedo(p.base_color, p.albedo);
+        let specular = metal * p.metallic + dielectric * (1.0 - p.metallic);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base = p.base_color * (white - dielectric) * (1.0 - p.metallic);
+        (specular, base)
+    }
+
+    // Chance of sampling the specular lobe rather than the base
+    fn specular_probability(p: &SurfaceParams) -> f64 {
+        let (specular, base) = Self::lobe_albedos(p);
+        let (specular, base) = (luminance(specular).max(0.0), luminance(base).max(0.0));
+        if specular + base <= 0.0 { 1.0 } else { specular / (specular + base) }
+    }
+
+    // BSDF times the cosine term, in the local frame
+    fn evaluate(p: &SurfaceParams, wo: Vec3, wi: Vec3) -> Color {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let h = Vec3::unit_vector(&(wo + wi));
+        let cos_h = Vec3::dot(wo, h);
+
+        let dielectric_f0 = Color::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0);
+        let metal = fresnel_schlick(p.base_color, cos_h) * Self::compensation(p.base_color, p.albedo);
+        let dielectric = fresnel_schlick(dielectric_f0, cos_h) * Self::compensation(dielectric_f0, p.albedo);
+        let fresnel = metal * p.metallic + dielectric * (1.0 - p.metallic);
+        let specular = fresnel * (ggx_d(h, p.alpha) * smith_g2(wo, wi, p.alpha) / (4.0 * wo.z()));
+
+        let (_, base) = Self::lobe_albedos(p);
+        specular + base * (wi.z() / PI)
+    }
+
+    fn pdf(p: &SurfaceParams, wo: Vec3, wi: Vec3) -> f64 {
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let h = Vec3::unit_vector(&(wo + wi));
+        let specular = Self::specular_probability(p);
+        specular * vndf_reflection_pdf(wo, h, p.alpha) + (1.0 - specular) * wi.z() / PI
+    }
+}
+
+impl Material for MetallicRoughness {
+    fn sample(&self, r_in: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler) -> Option<BsdfSample> {
+        let frame = shading_frame(rec);
+        let wo = Self::to_local(frame, -Vec3::unit_vector(&r_in.direction()));
+        if wo.z() <= 0.0 {
+            return None;
+        }
+        let p = self.params(rec, wo.z());
+
+        let (wi, lobe) = if sampler.next_1d() < Self::specular_probability(&p) {
+            let h = sample_vndf(wo, p.alpha, sampler.next_2d());
+            (Vec3::reflect(-wo, h), Lobe::Specular)
+        } else {
+            (cosine_hemisphere(sampler.next_2d()), Lobe::Diffuse)
+        };
+        // Reflections off microfacets can point below the surface; that light is lost
+        let pdf = Self::pdf(&p, wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+
+        let (t, b, n) = frame;
+        Some(BsdfSample {
+            wi: Vec3::unit_vector(&(t * wi.x() + b * wi.y() + n * wi.z())),
+            value: Self::evaluate(&p, wo, wi),
+            pdf,
+            is_specular: false,
+            lobe,
+        })
+    }
+
+    // Both lobes' directional albedos, which the sample weights average to
+    fn albedo(&self, r_in: &Ray, rec: &HitRecord) -> Color {
+        let wo = Self::to_local(shading_frame(rec), -Vec3::unit_vector(&r_in.direction()));
+        if wo.z() <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let (specular, base) = Self::lobe_albedos(&self.params(rec, wo.z()));
+        specular + base
+    }
+
+    fn is_specular(&self) -> bool {
+        false
+    }
+
+    fn scattering_value(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> Color {
+        let frame = shading_frame(rec);
+        let wo = Self::to_local(frame, -Vec3::unit_vector(&r_in.direction()));
+        let p = self.params(rec, wo.z());
+        Self::evaluate(&p, wo, Self::to_local(frame, wi))
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, rec: &HitRecord, wi: Vec3) -> f64 {
+        let frame = shading_frame(rec);
+        let wo = Self::to_local(frame, -Vec3::unit_vector(&r_in.direction()));
+        let p = self.params(rec, wo.z());
+        Self::pdf(&p, wo, Self::to_local(frame, wi))
+    }
+}
@@ -0,0 +1,160 @@
+use std::ops::Mul;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+
+// Row-major 4x4 matrix for affine transforms; points are columns (x, y, z, 1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    pub m: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Matrix4 = Matrix4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(offset: Vec3) -> Self {
+        let mut result = Self::IDENTITY;
+        result.m[0][3] = offset.x();
+        result.m[1][3] = offset.y();
+        result.m[2][3] = offset.z();
+        result
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        let mut result = Self::IDENTITY;
+        result.m[0][0] = factors.x();
+        result.m[1][1] = factors.y();
+        result.m[2][2] = factors.z();
+        result
+    }
+
+    // Right-handed rotations by an angle in degrees
+    pub fn rotation_x(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        Self::from_rows([[1.0, 0.0, 0.0], [0.0, c, -s], [0.0, s, c]])
+    }
+
+    pub fn rotation_y(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        Self::from_rows([[c, 0.0, s], [0.0, 1.0, 0.0], [-s, 0.0, c]])
+    }
+
+    pub fn rotation_z(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        Self::from_rows([[c, -s, 0.0], [s, c, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    // Rotation about an arbitrary axis (Rodrigues' formula)
+    pub fn rotation(axis: Vec3, degrees: f64) -> Self {
+        let a = Vec3::unit_vector(&axis);
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (s, c) = degrees.to_radians().sin_cos();
+        let t = 1.0 - c;
+        Self::from_rows([
+            [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+            [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+            [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+        ])
+    }
+
+    // Linear part only, no translation
+    fn from_rows(rows: [[f64; 3]; 3]) -> Self {
+        let mut result = Self::IDENTITY;
+        for (i, row) in rows.iter().enumerate() {
+            result.m[i][..3].copy_from_slice(row);
+        }
+        result
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::IDENTITY;
+        for i in 0..4 {
+            for j in 0..4 {
+                result.m[i][j] = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    // Gauss-Jordan elimination with partial pivoting; None when the matrix is singular
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::IDENTITY.m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Self { m: inv })
+    }
+
+    #[inline]
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.m;
+        Point3::new(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    #[inline]
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    // Normals go through the inverse transpose. Called on the inverse of the transform
+    // that moved the surface, so only the transpose is left to apply.
+    #[inline]
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * n.x() + m[1][0] * n.y() + m[2][0] * n.z(),
+            m[0][1] * n.x() + m[1][1] * n.y() + m[2][1] * n.z(),
+            m[0][2] * n.x() + m[1][2] * n.y() + m[2][2] * n.z(),
+        )
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Matrix4;
+
+    // self * rhs applies rhs first
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut result = [[0.0; 4]; 4];
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Matrix4 { m: result }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::hittable_list::HittableList;
+use crate::ray_tracer::bvh::BvhNode;
+use crate::ray_tracer::triangle::Triangle;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::aabb::Aabb;
+use std::sync::Arc;
+
+// A triangle soup with its own BVH, so a mesh behaves like a single object in the world
+pub struct Mesh {
+    bvh: BvhNode,
+    triangle_count: usize,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let triangle_count = triangles.len();
+        let mut list = HittableList::new();
+        for triangle in triangles {
+            list.add(Arc::new(triangle));
+        }
+        Self { bvh: BvhNode::new(list), triangle_count }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangle_count
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        self.bvh.hit(r, t_range, rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+}
@@ -0,0 +1,136 @@
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+use crate::ray_tracer::vec3::{Color, Vec3};
+
+// Isotropic GGX (Trowbridge-Reitz) microfacets with height-correlated Smith masking.
+// Directions are unit vectors in a local frame with the normal along +z, and `alpha` is
+// the width of the distribution (roughness squared in the glTF convention).
+
+// Narrowest lobe allowed; a perfect mirror would make the density a delta
+pub const MIN_ALPHA: f64 = 1e-3;
+
+// Density of microfacet normals h, per unit projected area
+#[inline]
+pub fn ggx_d(h: Vec3, alpha: f64) -> f64 {
+    let a2 = alpha * alpha;
+    let denom = h.z() * h.z() * (a2 - 1.0) + 1.0;
+    a2 / (PI * denom * denom)
+}
+
+// Smith's auxiliary function for GGX
+#[inline]
+fn smith_lambda(w: Vec3, alpha: f64) -> f64 {
+    let cos2 = w.z() * w.z();
+    if cos2 <= 0.0 {
+        return f64::INFINITY;
+    }
+    let tan2 = (1.0 - cos2).max(0.0) / cos2;
+    ((1.0 + alpha * alpha * tan2).sqrt() - 1.0) / 2.0
+}
+
+// Fraction of the microfacets facing `w` that are not hidden by others
+#[inline]
+pub fn smith_g1(w: Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(w, alpha))
+}
+
+// Fraction visible from both directions, with masking and shadowing correlated by height
+#[inline]
+pub fn smith_g2(wo: Vec3, wi: Vec3, alpha: f64) -> f64 {
+    1.0 / (1.0 + smith_lambda(wo, alpha) + smith_lambda(wi, alpha))
+}
+
+// Schlick's approximation, with `f0` the reflectance at normal incidence
+#[inline]
+pub fn fresnel_schlick(f0: Color, cosine: f64) -> Color {
+    let white = Color::new(1.0, 1.0, 1.0);
+    f0 + (white - f0) * (1.0 - cosine.clamp(0.0, 1.0)).powi(5)
+}
+
+// Picks a microfacet normal among those visible from wo, in proportion to how much of
+// the view each covers (Heitz 2018, "Sampling the GGX Distribution of Visible Normals")
+pub fn sample_vndf(wo: Vec3, alpha: f64, (u1, u2): (f64, f64)) -> Vec3 {
+    // Stretch to the hemisphere configuration where the distribution is a unit sphere
+    let vh = Vec3::unit_vector(&Vec3::new(alpha * wo.x(), alpha * wo.y(), wo.z()));
+    let len2 = vh.x() * vh.x() + vh.y() * vh.y();
+    let t1 = if len2 > 0.0 {
+        Vec3::new(-vh.y(), vh.x(), 0.0) / len2.sqrt()
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let t2 = Vec3::cross(vh, t1);
+
+    // Uniform point on the projected disk, squashed onto the visible half
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let p1 = r * phi.cos();
+    let s = 0.5 * (1.0 + vh.z());
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+    let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+    Vec3::unit_vector(&Vec3::new(alpha * nh.x(), alpha * nh.y(), nh.z().max(1e-9)))
+}
+
+// Solid angle density of the direction reflected about a `sample_vndf` normal h
+#[inline]
+pub fn vndf_reflection_pdf(wo: Vec3, h: Vec3, alpha: f64) -> f64 {
+    if wo.z() <= 0.0 {
+        return 0.0;
+    }
+    smith_g1(wo, alpha) * ggx_d(h, alpha) / (4.0 * wo.z())
+}
+
+const TABLE_SIZE: usize = 32;
+const TABLE_SAMPLES_PER_AXIS: usize = 32;
+
+// Directional albedo of a single-scattering GGX lobe seen from elevation cosine `cos_o`:
+// (E, S) where E is the albedo with a Fresnel term of one and S the part carried by
+// Schlick's (1 - cos)^5, so a lobe with Fresnel-Schlick reflects f0 * E + (1 - f0) * S.
+// Whatever is missing from E is light that would have scattered between microfacets
+// more than once.
+pub fn directional_albedo(cos_o: f64, alpha: f64) -> (f64, f64) {
+    let table = albedo_table();
+    let x = cos_o.clamp(0.0, 1.0) * (TABLE_SIZE - 1) as f64;
+    let y = alpha.clamp(0.0, 1.0) * (TABLE_SIZE - 1) as f64;
+    let (i, j) = ((x as usize).min(TABLE_SIZE - 2), (y as usize).min(TABLE_SIZE - 2));
+    let (fx, fy) = (x - i as f64, y - j as f64);
+
+    let at = |i: usize, j: usize| table[j * TABLE_SIZE + i];
+    let lerp = |a: (f64, f64), b: (f64, f64), t: f64| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+    lerp(lerp(at(i, j), at(i + 1, j), fx), lerp(at(i, j + 1), at(i + 1, j + 1), fx), fy)
+}
+
+// (E, S) over a grid of cos_o by alpha, both from 0 to 1, integrated once by sampling
+// visible normals on a stratified grid
+fn albedo_table() -> &'static [(f64, f64)] {
+    static TABLE: OnceLock<Vec<(f64, f64)>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let n = TABLE_SAMPLES_PER_AXIS;
+        let mut table = Vec::with_capacity(TABLE_SIZE * TABLE_SIZE);
+        for j in 0..TABLE_SIZE {
+            let alpha = (j as f64 / (TABLE_SIZE - 1) as f64).max(MIN_ALPHA);
+            for i in 0..TABLE_SIZE {
+                let cos_o = (i as f64 / (TABLE_SIZE - 1) as f64).max(1e-3);
+                let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+                let (mut e, mut s) = (0.0, 0.0);
+                for a in 0..n {
+                    for b in 0..n {
+                        let u = ((a as f64 + 0.5) / n as f64, (b as f64 + 0.5) / n as f64);
+                        let h = sample_vndf(wo, alpha, u);
+                        let wi = Vec3::reflect(-wo, h);
+                        if wi.z() <= 0.0 {
+                            continue;
+                        }
+                        // BSDF times cosine over the pdf, with F = 1
+                        let weight = smith_g2(wo, wi, alpha) / smith_g1(wo, alpha);
+                        e += weight;
+                        s += weight * (1.0 - Vec3::dot(wo, h).clamp(0.0, 1.0)).powi(5);
+                    }
+                }
+                let count = (n * n) as f64;
+                table.push((e / count, s / count));
+            }
+        }
+        table
+    })
+}
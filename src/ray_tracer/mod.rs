@@ -7,4 +7,35 @@ pub mod sphere;
 pub mod interval;
 pub mod camera;
 pub mod pixel_data;
-pub mod denoiser;
\ No newline at end of file
+pub mod denoiser;
+pub mod sampler;
+pub mod material;
+pub mod aabb;
+pub mod bvh;
+pub mod image_writer;
+pub mod scene;
+pub mod triangle;
+pub mod mesh;
+pub mod obj_loader;
+pub mod integrator;
+pub mod renderer;
+pub mod quad;
+pub mod light;
+pub mod environment;
+pub mod tile;
+pub mod tonemap;
+pub mod aov;
+pub mod temporal;
+pub mod adaptive;
+pub mod aperture;
+pub mod plane;
+pub mod disk;
+pub mod cuboid;
+pub mod cylinder;
+pub mod cone;
+pub mod torus;
+pub mod matrix;
+pub mod instance;
+pub mod perlin;
+pub mod texture;
+pub mod microfacet;
\ No newline at end of file
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ray_tracer::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight};
+use crate::ray_tracer::mesh::Mesh;
+use crate::ray_tracer::texture::{ConstantTexture, ImageTexture, Texture, WrapMode};
+use crate::ray_tracer::triangle::Triangle;
+use crate::ray_tracer::vec3::{Color, Point3, Vec3};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, line: usize, message: String },
+    // A texture map named in an MTL file that could not be loaded
+    Texture { path: PathBuf, line: usize, message: String },
+    Empty { path: PathBuf },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse { path, line, message } | ObjError::Texture { path, line, message } => {
+                write!(f, "{}:{}: {}", path.display(), line, message)
+            }
+            ObjError::Empty { path } => write!(f, "{}: no faces found", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// One corner of a face: indices into the position, uv and normal arrays
+#[derive(Clone, Copy)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+// Loads a Wavefront OBJ file into a mesh. Materials come from the referenced MTL
+// libraries; faces without a usable material fall back to `fallback_material`.
+// Polygons with more than three corners are fan-triangulated.
+pub fn load_obj(path: &Path, fallback_material: Arc<dyn Material>) -> Result<Mesh, ObjError> {
+    let source = fs::read_to_string(path).map_err(|source| ObjError::Io { path: path.to_path_buf(), source })?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+    let mut positions: Vec<Point3> = Vec::new();
+    let mut uvs: Vec<(f64, f64)> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut current_material = Arc::clone(&fallback_material);
+    let mut triangles = Vec::new();
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+
+        match keyword {
+            "v" => {
+                let v = parse_floats::<3>(&mut tokens).map_err(|e| parse_error(line_number, e))?;
+                positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                // The optional third texture coordinate is ignored
+                let vt = parse_floats::<2>(&mut tokens).map_err(|e| parse_error(line_number, e))?;
+                uvs.push((vt[0], vt[1]));
+            }
+            "vn" => {
+                let n = parse_floats::<3>(&mut tokens).map_err(|e| parse_error(line_number, e))?;
+                normals.push(Vec3::new(n[0], n[1], n[2]));
+            }
+            "f" => {
+                let corners = tokens
+                    .map(|token| parse_face_vertex(token, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| parse_error(line_number, e))?;
+                if corners.len() < 3 {
+                    return Err(parse_error(line_number, format!("face needs at least 3 vertices, got {}", corners.len())));
+                }
+
+                for i in 1..corners.len() - 1 {
+                    let corner = [corners[0], corners[i], corners[i + 1]];
+                    triangles.push(build_triangle(&corner, &positions, &uvs, &normals, &current_material));
+                }
+            }
+            "mtllib" => {
+                for name in tokens {
+                    let mtl_path = base_dir.join(name);
+                    match load_mtl(&mtl_path) {
+                        Ok(library) => materials.extend(library),
+                        // A missing library is only a warning, but a texture it names that can't be loaded is not
+                        Err(e @ ObjError::Texture { .. }) => return Err(e),
+                        Err(e) => log::warn!("{}:{}: skipping material library: {}", path.display(), line_number, e),
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = tokens.next().unwrap_or("");
+                current_material = match materials.get(name) {
+                    Some(material) => Arc::clone(material),
+                    None => {
+                        log::warn!("{}:{}: unknown material '{}', using the fallback", path.display(), line_number, name);
+                        Arc::clone(&fallback_material)
+                    }
+                };
+            }
+            // Groups, objects, smoothing groups, lines and points don't affect rendering
+            "o" | "g" | "s" | "l" | "p" => {}
+            other => log::debug!("{}:{}: ignoring '{}'", path.display(), line_number, other),
+        }
+    }
+
+    if triangles.is_empty() {
+        return Err(ObjError::Empty { path: path.to_path_buf() });
+    }
+    Ok(Mesh::new(triangles))
+}
+
+fn build_triangle(
+    corner: &[FaceVertex; 3],
+    positions: &[Point3],
+    uvs: &[(f64, f64)],
+    normals: &[Vec3],
+    material: &Arc<dyn Material>,
+) -> Triangle {
+    let mut triangle = Triangle::new(
+        positions[corner[0].position],
+        positions[corner[1].position],
+        positions[corner[2].position],
+        Arc::clone(material),
+    );
+
+    // Per-vertex attributes are only used when every corner provides them
+    if let (Some(a), Some(b), Some(c)) = (corner[0].normal, corner[1].normal, corner[2].normal) {
+        triangle = triangle.with_normals([normals[a], normals[b], normals[c]]);
+    }
+    if let (Some(a), Some(b), Some(c)) = (corner[0].uv, corner[1].uv, corner[2].uv) {
+        triangle = triangle.with_uvs([uvs[a], uvs[b], uvs[c]]);
+    }
+    triangle
+}
+
+fn parse_floats<const N: usize>(tokens: &mut std::str::SplitWhitespace) -> Result<[f64; N], String> {
+    let mut values = [0.0; N];
+    for value in values.iter_mut() {
+        let token = tokens.next().ok_or_else(|| format!("expected {} numbers", N))?;
+        *value = token.parse().map_err(|_| format!("invalid number '{}'", token))?;
+    }
+    Ok(values)
+}
+
+// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`, checking each index against what has been defined so far
+fn parse_face_vertex(token: &str, position_count: usize, uv_count: usize, normal_count: usize) -> Result<FaceVertex, String> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next(), position_count, "vertex")?
+        .ok_or_else(|| format!("face vertex '{}' has no position index", token))?;
+    let uv = resolve_index(parts.next(), uv_count, "texture coordinate")?;
+    let normal = resolve_index(parts.next(), normal_count, "normal")?;
+    Ok(FaceVertex { position, uv, normal })
+}
+
+// OBJ indices are 1-based, and negative indices count back from the most recent element
+fn resolve_index(part: Option<&str>, count: usize, kind: &str) -> Result<Option<usize>, String> {
+    let part = match part {
+        Some(p) if !p.is_empty() => p,
+        _ => return Ok(None),
+    };
+    let index: i64 = part.parse().map_err(|_| format!("invalid {} index '{}'", kind, part))?;
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{} index {} out of range ({} defined)", kind, index, count));
+    }
+    Ok(Some(resolved as usize))
+}
+
+#[derive(Default)]
+struct MtlDesc {
+    diffuse: Option<Color>,
+    // map_Kd, used in place of Kd
+    diffuse_map: Option<Arc<dyn Texture>>,
+    specular: Option<Color>,
+    emission: Option<Color>,
+    shininess: Option<f64>,
+    refraction_index: Option<f64>,
+    dissolve: Option<f64>,
+    illum: Option<u32>,
+}
+
+impl MtlDesc {
+    // Maps the classic MTL parameters onto the closest material this renderer has
+    fn into_material(self) -> Arc<dyn Material> {
+        let diffuse = self.diffuse.unwrap_or(Color::new(0.8, 0.8, 0.8));
+        let transparent = self.dissolve.is_some_and(|d| d < 1.0) || matches!(self.illum, Some(4 | 6 | 7 | 9));
+
+        if let Some(emission) = self.emission.filter(|e| !e.near_zero()) {
+            Arc::new(DiffuseLight::new(emission))
+        } else if transparent {
+            Arc::new(Dielectric::new(self.refraction_index.unwrap_or(1.5)))
+        } else if matches!(self.illum, Some(3 | 5 | 8)) {
+            // Phong exponent to an approximate roughness
+            let fuzz = (2.0 / (self.shininess.unwrap_or(0.0) + 2.0)).sqrt();
+            match (self.specular, self.diffuse_map) {
+                (None, Some(map)) => {
+                    Arc::new(Metal::textured(map, Arc::new(ConstantTexture::new(Color::new(fuzz, fuzz, fuzz)))))
+                }
+                (specular, _) => Arc::new(Metal::new(specular.unwrap_or(diffuse), fuzz)),
+            }
+        } else if let Some(map) = self.diffuse_map {
+            Arc::new(Lambertian::textured(map))
+        } else {
+            Arc::new(Lambertian::new(diffuse))
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, Arc<dyn Material>>, ObjError> {
+    let source = fs::read_to_string(path).map_err(|source| ObjError::Io { path: path.to_path_buf(), source })?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let parse_error = |line: usize, message: String| ObjError::Parse { path: path.to_path_buf(), line, message };
+
+    let mut library = HashMap::new();
+    let mut current: Option<(String, MtlDesc)> = None;
+
+    for (index, raw_line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+
+        if keyword == "newmtl" {
+            if let Some((name, desc)) = current.take() {
+                library.insert(name, desc.into_material());
+            }
+            let name = tokens.next().ok_or_else(|| parse_error(line_number, "newmtl without a name".to_string()))?;
+            current = Some((name.to_string(), MtlDesc::default()));
+            continue;
+        }
+
+        let Some((_, desc)) = current.as_mut() else {
+            return Err(parse_error(line_number, format!("'{}' before any newmtl", keyword)));
+        };
+        let color = |tokens: &mut std::str::SplitWhitespace| {
+            parse_floats::<3>(tokens).map(|c| Color::new(c[0], c[1], c[2]))
+        };
+
+        let result = match keyword {
+            "Kd" => color(&mut tokens).map(|c| desc.diffuse = Some(c)),
+            "Ks" => color(&mut tokens).map(|c| desc.specular = Some(c)),
+            "Ke" => color(&mut tokens).map(|c| desc.emission = Some(c)),
+            "Ns" => parse_floats::<1>(&mut tokens).map(|v| desc.shininess = Some(v[0])),
+            "Ni" => parse_floats::<1>(&mut tokens).map(|v| desc.refraction_index = Some(v[0])),
+            "d" => parse_floats::<1>(&mut tokens).map(|v| desc.dissolve = Some(v[0])),
+            "Tr" => parse_floats::<1>(&mut tokens).map(|v| desc.dissolve = Some(1.0 - v[0])),
+            "illum" => tokens
+                .next()
+                .and_then(|t| t.parse().ok())
+                .map(|v| desc.illum = Some(v))
+                .ok_or_else(|| "expected an illumination model number".to_string()),
+            // Map options such as -s and -o are skipped; the file name comes last
+            "map_Kd" => match tokens.last() {
+                Some(name) => {
+                    let image = ImageTexture::load(&base_dir.join(name), WrapMode::Repeat).map_err(|e| {
+                        ObjError::Texture { path: path.to_path_buf(), line: line_number, message: e.to_string() }
+                    })?;
+                    desc.diffuse_map = Some(Arc::new(image));
+                    Ok(())
+                }
+                None => Err("map_Kd without a file name".to_string()),
+            },
+            // Other texture maps and extensions are not supported yet
+            _ => Ok(()),
+        };
+        result.map_err(|e| parse_error(line_number, e))?;
+    }
+
+    if let Some((name, desc)) = current {
+        library.insert(name, desc.into_material());
+    }
+    Ok(library)
+}
@@ -0,0 +1,99 @@
+use crate::ray_tracer::sampler::mix;
+use crate::ray_tracer::vec3::Point3;
+
+// Ken Perlin's improved gradient noise over a lattice shuffled from a seed
+pub struct Perlin {
+    // The permutation twice over, so lookups never need wrapping
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        // Fisher-Yates
+        for i in (1..256).rev() {
+            let j = (mix(seed ^ mix(i as u64)) % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        Self { permutation: std::array::from_fn(|i| table[i % 256]) }
+    }
+
+    // Smooth noise in roughly [-1, 1], zero at every lattice point
+    pub fn noise(&self, p: Point3) -> f64 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (x, y, z) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let (xi, yi, zi) = ((fx as i64 & 255) as usize, (fy as i64 & 255) as usize, (fz as i64 & 255) as usize);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.permutation;
+        let a = perm[xi] as usize + yi;
+        let (aa, ab) = (perm[a] as usize + zi, perm[a + 1] as usize + zi);
+        let b = perm[xi + 1] as usize + yi;
+        let (ba, bb) = (perm[b] as usize + zi, perm[b + 1] as usize + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(perm[aa], x, y, z), grad(perm[ba], x - 1.0, y, z)),
+                lerp(u, grad(perm[ab], x, y - 1.0, z), grad(perm[bb], x - 1.0, y - 1.0, z)),
+            ),
+            lerp(
+                v,
+                lerp(u, grad(perm[aa + 1], x, y, z - 1.0), grad(perm[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, grad(perm[ab + 1], x, y - 1.0, z - 1.0), grad(perm[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+            ),
+        )
+    }
+
+    // Fractional Brownian motion: octaves at doubling frequency and halving amplitude,
+    // normalized back to roughly [-1, 1]
+    pub fn fbm(&self, p: Point3, octaves: u32) -> f64 {
+        let (mut sum, mut amplitude, mut total, mut q) = (0.0, 1.0, 0.0, p);
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise(q);
+            total += amplitude;
+            amplitude *= 0.5;
+            q = q * 2.0;
+        }
+        sum / total
+    }
+
+    // Like fbm over the absolute noise, in [0, 1] with creases where the noise crosses zero
+    pub fn turbulence(&self, p: Point3, octaves: u32) -> f64 {
+        let (mut sum, mut amplitude, mut total, mut q) = (0.0, 1.0, 0.0, p);
+        for _ in 0..octaves.max(1) {
+            sum += amplitude * self.noise(q).abs();
+            total += amplitude;
+            amplitude *= 0.5;
+            q = q * 2.0;
+        }
+        (sum / total).min(1.0)
+    }
+}
+
+// 6t^5 - 15t^4 + 10t^3, flat first and second derivatives at the lattice
+#[inline]
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline]
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Dot product with one of twelve gradients along the cube's edge midpoints
+#[inline]
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
@@ -1,8 +1,12 @@
+use crate::ray_tracer::environment::luminance;
 use crate::ray_tracer::vec3::{Vec3, Color};
 
 #[derive(Clone, Copy, Debug)]
 pub struct PixelData {
+    // Running mean of the samples
     pub color: Color,
+    // Sum of squared differences from the running mean (Welford's M2)
+    pub m2: Color,
     pub depth: f32,
     pub normal: Vec3,
     pub albedo: Color,
@@ -13,10 +17,64 @@ impl PixelData {
     pub fn new() -> Self {
         Self {
             color: Color::new(0.0, 0.0, 0.0),
+            m2: Color::new(0.0, 0.0, 0.0),
             depth: f32::INFINITY,
             normal: Vec3::new(0.0, 0.0, 0.0),
             albedo: Color::new(0.0, 0.0, 0.0),
             sample_count: 0,
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for PixelData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PixelData {
+    // Welford's update, which stays accurate where summing squares would cancel out
+    pub fn add_sample(&mut self, sample: Color) {
+        self.sample_count += 1;
+        let delta = sample - self.color;
+        self.color += delta / self.sample_count as f64;
+        self.m2 += delta * (sample - self.color);
+    }
+
+    // Adds `sample_count` samples summarized by their mean and M2 (Chan et al.)
+    pub fn merge(&mut self, mean: Color, m2: Color, sample_count: u32) {
+        if sample_count == 0 {
+            return;
+        }
+        let (n_a, n_b) = (self.sample_count as f64, sample_count as f64);
+        let n = n_a + n_b;
+        let delta = mean - self.color;
+        self.color += delta * (n_b / n);
+        self.m2 += m2 + delta * delta * (n_a * n_b / n);
+        self.sample_count += sample_count;
+    }
+
+    // Average of the samples taken so far, black before the first
+    pub fn mean(&self) -> Color {
+        self.color
+    }
+
+    // Unbiased per-channel variance of the samples taken so far
+    pub fn variance(&self) -> Color {
+        if self.sample_count < 2 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let variance = self.m2 / (self.sample_count - 1) as f64;
+        Color::new(variance.x().max(0.0), variance.y().max(0.0), variance.z().max(0.0))
+    }
+
+    // Standard error of the mean's luminance relative to the luminance itself; the
+    // small offset keeps nearly black pixels from never converging
+    pub fn relative_error(&self) -> f64 {
+        if self.sample_count < 2 {
+            return f64::INFINITY;
+        }
+        let standard_error = (luminance(self.variance()) / self.sample_count as f64).sqrt();
+        standard_error / (luminance(self.color) + 0.01)
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::light::orthonormal_basis;
+
+// How far an infinite plane reaches for the BVH; an unbounded box would break the splits
+const PLANE_EXTENT: f64 = 1e6;
+
+// Infinite plane through `point`, facing `normal`. UVs are the hit point's coordinates
+// along two tangent directions in world units, so textures tile across the plane.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        let normal = Vec3::unit_vector(&normal);
+        let (tangent, bitangent) = orthonormal_basis(normal);
+
+        // Flat along an axis the plane is perpendicular to, otherwise as wide as allowed
+        let n = [normal.x(), normal.y(), normal.z()];
+        let p = [point.x(), point.y(), point.z()];
+        let axis = |i: usize| {
+            if n[i].abs() > 1.0 - 1e-9 {
+                Interval::new(p[i], p[i])
+            } else {
+                Interval::new(-PLANE_EXTENT, PLANE_EXTENT)
+            }
+        };
+        let bbox = Aabb::new(axis(0), axis(1), axis(2));
+
+        Plane { point, normal, tangent, bitangent, material, bbox }
+    }
+}
+
+impl Hittable for Plane {
+    #[inline]
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let denom = Vec3::dot(self.normal, r.direction());
+
+        // Ray is parallel to the plane
+        if denom.abs() < 1e-12 {
+            return false;
+        }
+
+        let t = Vec3::dot(self.point - r.origin(), self.normal) / denom;
+        if t < t_range.min || t > t_range.max {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_outward_normal(r, self.normal);
+        rec.set_tangent(self.tangent);
+        let planar = rec.p - self.point;
+        rec.u = Vec3::dot(planar, self.tangent);
+        rec.v = Vec3::dot(planar, self.bitangent);
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+
+// Parallelogram spanned by the edges u and v from corner q
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    normal: Vec3,
+    d: f64,
+    area: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = Vec3::cross(u, v);
+        let normal = Vec3::unit_vector(&n);
+        let bbox = Aabb::surrounding(
+            &Aabb::from_points(q, q + u + v),
+            &Aabb::from_points(q + u, q + v),
+        );
+        Quad {
+            q,
+            u,
+            v,
+            w: n / Vec3::dot(n, n),
+            normal,
+            d: Vec3::dot(normal, q),
+            area: n.length(),
+            material,
+            bbox,
+        }
+    }
+
+    pub fn corner(&self) -> Point3 {
+        self.q
+    }
+
+    pub fn edges(&self) -> (Vec3, Vec3) {
+        (self.u, self.v)
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+}
+
+impl Hittable for Quad {
+    #[inline]
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let denom = Vec3::dot(self.normal, r.direction());
+
+        // Ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+
+        let t = (self.d - Vec3::dot(self.normal, r.origin())) / denom;
+        if t < t_range.min || t > t_range.max {
+            return false;
+        }
+
+        // Planar coordinates of the hit point along u and v
+        let p = r.at(t);
+        let planar = p - self.q;
+        let alpha = Vec3::dot(self.w, Vec3::cross(planar, self.v));
+        let beta = Vec3::dot(self.w, Vec3::cross(self.u, planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.set_outward_normal(r, self.normal);
+        rec.set_tangent(self.u);
+        rec.u = alpha;
+        rec.v = beta;
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,482 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::Instant;
+use rayon::prelude::*;
+
+use crate::ray_tracer::adaptive::{self, AdaptiveSettings};
+use crate::ray_tracer::aov::Aov;
+use crate::ray_tracer::vec3::Color;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::bvh::BvhNode;
+use crate::ray_tracer::camera::Camera;
+use crate::ray_tracer::integrator::{Integrator, PathStatistics, SceneView};
+use crate::ray_tracer::environment::Environment;
+use crate::ray_tracer::light::LightList;
+use crate::ray_tracer::pixel_data::PixelData;
+use crate::ray_tracer::denoiser::{Denoiser, DenoiserKind};
+use crate::ray_tracer::sampler::SamplerKind;
+use crate::ray_tracer::scene::Scene;
+use crate::ray_tracer::temporal::History;
+use crate::ray_tracer::tile::{self, Tile, TileProgress, TileSettings};
+use crate::ray_tracer::tonemap::ToneMapping;
+
+pub const DEFAULT_ASPECT_RATIO: f64 = 16.0 / 9.0;
+
+// Image height for a width at the given aspect ratio, never less than one pixel
+pub fn image_height_for(image_width: u32, aspect_ratio: f64) -> u32 {
+    ((image_width as f64 / aspect_ratio) as u32).max(1)
+}
+
+// Owns everything that accumulates over frames: the pixel buffer, the sample counter,
+// the denoiser and the sampler seed. What is traced comes from the scene, where from comes
+// from the camera passed to each frame, and how from the integrator.
+pub struct Renderer {
+    image_width: u32,
+    image_height: u32,
+    world: Arc<dyn Hittable>,
+    lights: LightList,
+    environment: Arc<dyn Environment>,
+    integrator: Box<dyn Integrator>,
+    pixel_buffer: Vec<PixelData>,
+    sample_count: AtomicU32,
+    current_frame: u32,
+    denoiser: Box<dyn Denoiser>,
+    denoiser_kind: DenoiserKind,
+    enable_denoising: bool,
+    tone_mapping: ToneMapping,
+
+    // The same seed, resolution and sample count always give the same pixels
+    seed: u64,
+    sampler: SamplerKind,
+
+    // Camera the accumulated samples belong to
+    last_camera: Option<Camera>,
+    // Samples from before the camera last moved, reprojected into the first new frame
+    history: Option<History>,
+    temporal_reprojection: bool,
+    // Converged pixels are skipped when set
+    adaptive: Option<AdaptiveSettings>,
+
+    tile_settings: TileSettings,
+    // Tiles of a frame in scheduling order
+    tiles: Vec<Tile>,
+    // First tile of the frame in flight not rendered yet; 0 when no frame is in flight
+    next_tile: usize,
+}
+
+pub enum FrameStatus {
+    Complete,
+    // Ran out of time; the rest of the frame follows on the next call
+    InProgress,
+}
+
+impl Renderer {
+    pub fn new(image_width: u32, image_height: u32, scene: Scene, integrator: Box<dyn Integrator>) -> Self {
+        let buffer_size = (image_width * image_height) as usize;
+
+        Self {
+            image_width,
+            image_height,
+            world: Arc::new(BvhNode::new(scene.world)),
+            lights: scene.lights,
+            environment: scene.environment,
+            integrator,
+            pixel_buffer: vec![PixelData::new(); buffer_size],
+            sample_count: AtomicU32::new(0),
+            current_frame: 0,
+            denoiser: DenoiserKind::default().create(image_width, image_height),
+            denoiser_kind: DenoiserKind::default(),
+            enable_denoising: false,
+            tone_mapping: ToneMapping::default(),
+            seed: 0,
+            sampler: SamplerKind::default(),
+            last_camera: None,
+            history: None,
+            temporal_reprojection: true,
+            adaptive: None,
+            tile_settings: TileSettings::default(),
+            tiles: tile::tiles(image_width, image_height, TileSettings::default()),
+            next_tile: 0,
+        }
+    }
+
+    pub fn image_width(&self) -> u32 {
+        self.image_width
+    }
+
+    pub fn image_height(&self) -> u32 {
+        self.image_height
+    }
+
+    pub fn aspect_ratio(&self) -> f64 {
+        self.image_width as f64 / self.image_height as f64
+    }
+
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+        self.reset_accumulation();
+    }
+    
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset_accumulation();
+    }
+
+    pub fn set_sampler(&mut self, sampler: SamplerKind) {
+        self.sampler = sampler;
+        self.reset_accumulation();
+    }
+
+    pub fn reset_accumulation(&mut self) {
+        self.pixel_buffer.fill(PixelData::new());
+        self.sample_count.store(0, Ordering::Relaxed);
+        self.current_frame = 0;
+        self.next_tile = 0;
+        self.history = None;
+        self.integrator.reset_statistics();
+    }
+
+    pub fn adaptive_sampling(&self) -> Option<AdaptiveSettings> {
+        self.adaptive
+    }
+
+    // Takes effect from the next frame; the samples so far are kept either way
+    pub fn set_adaptive_sampling(&mut self, adaptive: Option<AdaptiveSettings>) {
+        self.adaptive = adaptive;
+    }
+
+    // Share of pixels that meet the adaptive sampling threshold, None when it is off
+    pub fn converged_fraction(&self) -> Option<f64> {
+        let adaptive = self.adaptive?;
+        let converged = self.pixel_buffer.par_iter().filter(|p| adaptive.is_converged(p)).count();
+        Some(converged as f64 / self.pixel_buffer.len() as f64)
+    }
+
+    // True once adaptive sampling has nothing left to refine
+    pub fn is_converged(&self) -> bool {
+        self.converged_fraction() == Some(1.0)
+    }
+
+    pub fn is_temporal_reprojection_enabled(&self) -> bool {
+        self.temporal_reprojection
+    }
+
+    // Whether moving the camera carries the accumulated samples over to the new view
+    // instead of starting from scratch
+    pub fn set_temporal_reprojection(&mut self, enabled: bool) {
+        self.temporal_reprojection = enabled;
+    }
+
+    // Path counters of the integrator since the accumulation was last reset
+    pub fn integrator_statistics(&self) -> Option<PathStatistics> {
+        self.integrator.statistics()
+    }
+
+    // Adds one sample per pixel seen from `camera`, finishing any frame already in flight
+    pub fn render_progressive(&mut self, camera: &Camera) {
+        self.render_tiles(camera, None, &|_| {});
+    }
+
+    // Renders tiles of the current frame until the frame is done or `deadline` passes,
+    // calling `on_tile` from the worker threads as each tile finishes. A frame that runs
+    // out of time is picked up where it left off by the next call, unless the camera
+    // moved in between, which cancels it. Samples from a different camera (or the same
+    // camera after it moved) are discarded first, though with temporal reprojection on
+    // the first new frame takes over what is still visible of them.
+    pub fn render_tiles(
+        &mut self,
+        camera: &Camera,
+        deadline: Option<Instant>,
+        on_tile: &(dyn Fn(TileProgress) + Sync),
+    ) -> FrameStatus {
+        if self.last_camera.as_ref() != Some(camera) {
+            let previous = self.last_camera.replace(camera.clone());
+            // Swap buffers with the old history rather than allocating a new one
+            let spare = match self.history.take() {
+                Some(history) => history.into_pixels(),
+                None => vec![PixelData::new(); self.pixel_buffer.len()],
+            };
+            let pixels = std::mem::replace(&mut self.pixel_buffer, spare);
+            self.reset_accumulation();
+            if let Some(previous) = previous.filter(|_| self.temporal_reprojection) {
+                self.history = Some(History::new(&previous, self.image_width, self.image_height, pixels));
+            }
+        }
+
+        if self.next_tile == 0 {
+            self.current_frame += 1;
+        }
+
+        // Calculate camera geometry for current frame
+        let viewport = camera.viewport();
+        let aperture = camera.aperture();
+        let scene = SceneView {
+            world: self.world.as_ref(),
+            lights: &self.lights,
+            environment: self.environment.as_ref(),
+        };
+        let integrator = self.integrator.as_ref();
+        let (image_width, image_height) = (self.image_width, self.image_height);
+        let (seed, sampler_kind) = (self.seed, self.sampler);
+        // Frames count from 1, sample indices from 0
+        let sample_index = self.current_frame - 1;
+        let total = self.tiles.len();
+        let first = self.next_tile;
+        let history = self.history.as_ref();
+        let adaptive = self.adaptive;
+
+        let render_tile = |tile: Tile, mut rows: Vec<&mut [PixelData]>| {
+            let mut sampler = sampler_kind.create(seed);
+            // Camera rays of pixels taking their first sample since the camera moved
+            let mut fresh = Vec::new();
+            for (row, pixels) in rows.iter_mut().enumerate() {
+                let j = tile.y + row as u32;
+                for (column, pixel) in pixels.iter_mut().enumerate() {
+                    if adaptive.is_some_and(|a| a.is_converged(pixel)) {
+                        continue;
+                    }
+                    let i = tile.x + column as u32;
+                    sampler.start_pixel_sample(i, j, sample_index);
+
+                    let (du, dv) = sampler.next_2d();
+                    let u_offset = (i as f64 + du) / image_width as f64;
+                    let v_offset = ((image_height - 1 - j) as f64 + dv) / image_height as f64;
+
+                    let ray = if viewport.has_lens() {
+                        viewport.get_lens_ray(u_offset, v_offset, aperture.sample(sampler.next_2d()))
+                    } else {
+                        viewport.get_ray(u_offset, v_offset)
+                    };
+                    let (sample_color, sample_data) = integrator.radiance(&ray, &scene, sampler.as_mut());
+                    if history.is_some() && pixel.sample_count == 0 {
+                        fresh.push((row, column, ray));
+                    }
+
+                    // Accumulate straight into the pixel buffer
+                    pixel.add_sample(sample_color);
+                    pixel.depth = sample_data.depth;
+                    pixel.normal = sample_data.normal;
+                    pixel.albedo = sample_data.albedo;
+                }
+            }
+
+            // Blend in the history once the whole tile has its first sample, so each
+            // pixel has its neighbors to clamp against
+            if let Some(history) = history {
+                let samples: Vec<Option<Color>> = rows
+                    .iter()
+                    .flat_map(|pixels| pixels.iter().map(|p| (p.sample_count == 1).then_some(p.color)))
+                    .collect();
+                let at = |row: i64, column: i64| {
+                    if row < 0 || column < 0 || row >= tile.height as i64 || column >= tile.width as i64 {
+                        None
+                    } else {
+                        samples[(row * tile.width as i64 + column) as usize]
+                    }
+                };
+                for (row, column, ray) in fresh {
+                    let neighborhood: Vec<Color> = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dy, dx)))
+                        .filter_map(|(dy, dx)| at(row as i64 + dy, column as i64 + dx))
+                        .collect();
+                    let pixel = &mut rows[row][column];
+                    if let Some(sample) = history.reproject(&ray, pixel) {
+                        sample.merge_into(pixel, &neighborhood);
+                    }
+                }
+            }
+        };
+
+        // Workers pull tiles in order from a shared queue, so the scheduling order is kept
+        let jobs = split_into_tiles(&mut self.pixel_buffer, image_width, &self.tiles, self.tile_settings.size);
+        let queue = Mutex::new(jobs.into_iter().skip(first));
+        let started = AtomicUsize::new(0);
+        let completed = AtomicUsize::new(first);
+
+        rayon::scope(|s| {
+            for _ in 0..rayon::current_num_threads() {
+                s.spawn(|_| loop {
+                    let job = {
+                        let mut queue = queue.lock().unwrap();
+                        // Always make some progress, even with an expired deadline
+                        let out_of_time = deadline.is_some_and(|d| Instant::now() >= d);
+                        if out_of_time && started.load(Ordering::Relaxed) > 0 {
+                            None
+                        } else {
+                            started.fetch_add(1, Ordering::Relaxed);
+                            queue.next()
+                        }
+                    };
+                    let Some((tile, rows)) = job else { break };
+                    render_tile(tile, rows);
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    on_tile(TileProgress { tile, completed: done, total });
+                });
+            }
+        });
+
+        self.next_tile = completed.into_inner();
+        if self.next_tile < total {
+            return FrameStatus::InProgress;
+        }
+        self.next_tile = 0;
+        self.sample_count.fetch_add(1, Ordering::Relaxed);
+        FrameStatus::Complete
+    }
+
+    pub fn set_tile_settings(&mut self, settings: TileSettings) {
+        self.tile_settings = settings;
+        self.tiles = tile::tiles(self.image_width, self.image_height, settings);
+        // Tiles of a frame in flight would no longer line up
+        self.reset_accumulation();
+    }
+
+    // Accumulated radiance per pixel, denoised if enabled, before any tone mapping
+    pub fn render_hdr(&self) -> Vec<Color> {
+        self.render_hdr_with(self.enable_denoising)
+    }
+
+    fn render_hdr_with(&self, denoise: bool) -> Vec<Color> {
+        let sample_count = self.sample_count.load(Ordering::Relaxed);
+
+        // Pixels of an unfinished frame have one sample more than the rest,
+        // so each pixel is averaged over its own count
+        if denoise && sample_count >= 4 {
+            self.denoiser.denoise(&self.pixel_buffer)
+        } else {
+            self.pixel_buffer.iter().map(PixelData::mean).collect()
+        }
+    }
+
+    // Display pixels: the HDR image after exposure, tone mapping and sRGB encoding
+    pub fn render_rgba(&self) -> Vec<u8> {
+        self.render_rgba_with(self.enable_denoising)
+    }
+
+    // Like `render_rgba`, but denoised or not regardless of the toggle
+    pub fn render_rgba_with(&self, denoise: bool) -> Vec<u8> {
+        self.render_hdr_with(denoise)
+            .par_iter()
+            .flat_map_iter(|&color| self.tone_mapping.to_rgba8(color))
+            .collect()
+    }
+
+    // Focuses `camera` on what the G-buffer shows at the center of the image: the median
+    // depth of the 3x3 center pixels. Changes below 2% are ignored so the focus settles
+    // instead of following the noise. Returns whether the focus distance changed.
+    pub fn autofocus(&self, camera: &mut Camera) -> bool {
+        let (cx, cy) = (self.image_width / 2, self.image_height / 2);
+        let mut depths: Vec<f64> = (cy.saturating_sub(1)..=(cy + 1).min(self.image_height - 1))
+            .flat_map(|y| (cx.saturating_sub(1)..=(cx + 1).min(self.image_width - 1)).map(move |x| (x, y)))
+            .map(|(x, y)| &self.pixel_buffer[(y * self.image_width + x) as usize])
+            .filter(|p| p.sample_count > 0 && p.depth.is_finite())
+            .map(|p| p.depth as f64)
+            .collect();
+        if depths.is_empty() {
+            return false;
+        }
+        depths.sort_by(f64::total_cmp);
+        let depth = depths[depths.len() / 2];
+        if (depth - camera.focus_distance()).abs() <= 0.02 * camera.focus_distance() {
+            return false;
+        }
+        camera.set_focus_distance(depth);
+        true
+    }
+
+    // Debug view of where the samples went: each pixel's sample count relative to the
+    // largest one, colored from black (none) to white (the most)
+    pub fn render_heatmap(&self) -> Vec<u8> {
+        let most = self.pixel_buffer.iter().map(|p| p.sample_count).max().unwrap_or(0).max(1);
+        self.pixel_buffer
+            .par_iter()
+            .flat_map_iter(|p| adaptive::heatmap_color(p.sample_count as f64 / most as f64))
+            .collect()
+    }
+
+    // The linear beauty image followed by every G-buffer pass
+    pub fn aovs(&self) -> Vec<Aov> {
+        let pixels = &self.pixel_buffer;
+        let rgb = |c: Color| [c.x() as f32, c.y() as f32, c.z() as f32];
+        let collect = |f: &dyn Fn(&PixelData) -> [f32; 3]| pixels.iter().flat_map(f).collect::<Vec<f32>>();
+
+        vec![
+            Aov::new("beauty", &["R", "G", "B"], self.render_hdr().into_iter().flat_map(rgb).collect()),
+            Aov::new("depth", &["Z"], pixels.iter().map(|p| p.depth).collect()),
+            Aov::new("normal", &["X", "Y", "Z"], collect(&|p| rgb(p.normal))),
+            Aov::new("albedo", &["R", "G", "B"], collect(&|p| rgb(p.albedo))),
+            Aov::new("samples", &["Y"], pixels.iter().map(|p| p.sample_count as f32).collect()),
+            Aov::new("variance", &["R", "G", "B"], collect(&|p| rgb(p.variance()))),
+        ]
+    }
+
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping
+    }
+
+    // Only changes how the accumulated image is displayed, so no samples are lost
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+    }
+    
+    // Number of completed frames, i.e. samples per pixel
+    pub fn get_sample_count(&self) -> u32 {
+        self.sample_count.load(Ordering::Relaxed)
+    }
+    
+    pub fn get_pixel_data(&self) -> &[PixelData] {
+        &self.pixel_buffer
+    }
+
+    pub fn is_denoising_enabled(&self) -> bool {
+        self.enable_denoising
+    }
+
+    pub fn toggle_denoising(&mut self) {
+        self.enable_denoising = !self.enable_denoising;
+    }
+
+    pub fn set_denoising(&mut self, enabled: bool) {
+        self.enable_denoising = enabled;
+    }
+
+    pub fn denoiser(&self) -> DenoiserKind {
+        self.denoiser_kind
+    }
+
+    // Filtering happens on display, so switching keeps the accumulated samples
+    pub fn set_denoiser(&mut self, kind: DenoiserKind) {
+        self.denoiser = kind.create(self.image_width, self.image_height);
+        self.denoiser_kind = kind;
+    }
+}
+
+// Hands out the rows of every tile as disjoint slices of the buffer, in the order of `tiles`
+fn split_into_tiles<'a>(
+    buffer: &'a mut [PixelData],
+    image_width: u32,
+    tiles: &[Tile],
+    tile_size: u32,
+) -> Vec<(Tile, Vec<&'a mut [PixelData]>)> {
+    let columns = image_width.div_ceil(tile_size) as usize;
+    let mut slot = vec![0; tiles.len()];
+    for (position, tile) in tiles.iter().enumerate() {
+        slot[(tile.y / tile_size) as usize * columns + (tile.x / tile_size) as usize] = position;
+    }
+
+    let mut jobs: Vec<(Tile, Vec<&mut [PixelData]>)> = tiles.iter().map(|&tile| (tile, Vec::new())).collect();
+    for (j, mut row) in buffer.chunks_mut(image_width as usize).enumerate() {
+        let tile_row = j / tile_size as usize;
+        for column in 0..columns {
+            let width = row.len().min(tile_size as usize);
+            let (left, rest) = row.split_at_mut(width);
+            jobs[slot[tile_row * columns + column]].1.push(left);
+            row = rest;
+        }
+    }
+    jobs
+}
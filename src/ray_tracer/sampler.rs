@@ -0,0 +1,433 @@
+use std::f64::consts::PI;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::ray_tracer::vec3::Vec3;
+
+// Source of the random numbers for one pixel sample. After `start_pixel_sample` the values
+// that follow depend only on the seed, the pixel and the sample index, never on which
+// thread renders the pixel or in what order, so renders are reproducible.
+pub trait Sampler: Send {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32);
+
+    // Next value in [0, 1)
+    fn next_1d(&mut self) -> f64;
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let u = self.next_1d();
+        (u, self.next_1d())
+    }
+
+    fn random_in_unit_sphere(&mut self) -> Vec3 {
+        let direction = self.random_unit_vector();
+        direction * self.next_1d().cbrt()
+    }
+
+    fn random_unit_vector(&mut self) -> Vec3 {
+        let (u, v) = self.next_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
+    }
+}
+
+// Uncorrelated pseudo-random numbers from a 64-bit LCG, reseeded for every pixel sample
+pub struct IndependentSampler {
+    seed: u64,
+    state: u64,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: mix(seed) }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        let pixel = (u64::from(y) << 32) | u64::from(x);
+        self.state = mix(self.seed ^ mix(pixel ^ mix(u64::from(sample_index))));
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let result = (self.state >> 32) as u32;
+        (result as f64) * (1.0 / 4294967296.0)
+    }
+}
+
+// SplitMix64 finalizer; spreads nearby inputs (neighbouring pixels, consecutive samples) apart
+pub fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0, |h, &v| mix(h ^ v))
+}
+
+// Maps the top 53 bits of a hash to [0, 1)
+fn hash_to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+fn u32_to_unit(x: u32) -> f64 {
+    x as f64 * (1.0 / 4294967296.0)
+}
+
+fn pixel_key(x: u32, y: u32) -> u64 {
+    (u64::from(y) << 32) | u64::from(x)
+}
+
+// Jittered samples: each dimension (or pair of dimensions) is split into strata_per_axis^2
+// cells and every run of that many consecutive samples visits each cell once, in an order
+// shuffled per pixel and dimension. Works best when the sample count is a multiple of the
+// cell count.
+pub struct StratifiedSampler {
+    seed: u64,
+    strata_per_axis: u32,
+    pixel: u64,
+    sample_index: u32,
+    dimension: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, strata_per_axis: u32) -> Self {
+        Self { seed, strata_per_axis: strata_per_axis.max(1), pixel: 0, sample_index: 0, dimension: 0 }
+    }
+
+    // Cell of the current sample in this dimension, and a hash for jittering inside it
+    fn cell(&mut self, cells: u32) -> (u32, u64) {
+        let dimension = u64::from(self.dimension);
+        self.dimension += 1;
+        let pass = u64::from(self.sample_index / cells);
+        let h = hash(&[self.seed, self.pixel, dimension, pass]);
+        let cell = permute(self.sample_index % cells, cells, h as u32);
+        (cell, mix(h))
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.pixel = pixel_key(x, y);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let cells = self.strata_per_axis * self.strata_per_axis;
+        let (cell, h) = self.cell(cells);
+        (cell as f64 + hash_to_unit(h)) / cells as f64
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let n = self.strata_per_axis;
+        let (cell, h) = self.cell(n * n);
+        let (cx, cy) = (cell % n, cell / n);
+        (
+            (cx as f64 + hash_to_unit(h)) / n as f64,
+            (cy as f64 + hash_to_unit(mix(h))) / n as f64,
+        )
+    }
+}
+
+// Kensler's hash-based permutation of 0..len, from "Correlated Multi-Jittered Sampling"
+fn permute(mut i: u32, len: u32, p: u32) -> u32 {
+    if len <= 1 {
+        return 0;
+    }
+    let mut w = len - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < len {
+            return i.wrapping_add(p) % len;
+        }
+    }
+}
+
+// Owen-scrambled Sobol points, following Burley's "Practical Hash-based Owen Scrambling".
+// Every dimension pair uses the first two Sobol dimensions, decorrelated from the other
+// pairs by shuffling the sample index and scrambling the bits with different hashes.
+pub struct SobolSampler {
+    seed: u64,
+    pixel_seed: u64,
+    sample_index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, pixel_seed: 0, sample_index: 0, dimension: 0 }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.pixel_seed = hash(&[self.seed, pixel_key(x, y)]);
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.next_2d().0
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let dimension = u64::from(self.dimension);
+        self.dimension += 1;
+        let (x, y) = scrambled_sobol_2d(self.sample_index, hash(&[self.pixel_seed, dimension]));
+        (u32_to_unit(x), u32_to_unit(y))
+    }
+}
+
+fn scrambled_sobol_2d(index: u32, seed: u64) -> (u32, u32) {
+    let index = nested_uniform_scramble(index, seed as u32);
+    let h = mix(seed);
+    (
+        nested_uniform_scramble(sobol_0(index), h as u32),
+        nested_uniform_scramble(sobol_1(index), (h >> 32) as u32),
+    )
+}
+
+// First Sobol dimension: the van der Corput sequence
+fn sobol_0(index: u32) -> u32 {
+    index.reverse_bits()
+}
+
+// Second Sobol dimension, whose generator matrix is Pascal's triangle mod 2
+fn sobol_1(mut index: u32) -> u32 {
+    let mut v = 1u32 << 31;
+    let mut x = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            x ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    x
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+// Every pixel walks the same scrambled Sobol sequence, offset (Cranley-Patterson rotated)
+// by a tiled blue-noise mask. Neighbouring pixels then err in opposite directions, which
+// leaves high-frequency noise that looks finer and denoises better at low sample counts.
+pub struct BlueNoiseSampler {
+    seed: u64,
+    x: u32,
+    y: u32,
+    sample_index: u32,
+    dimension: u32,
+}
+
+const BLUE_NOISE_SIZE: usize = 64;
+
+impl BlueNoiseSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, x: 0, y: 0, sample_index: 0, dimension: 0 }
+    }
+
+    // Mask value for this pixel, with the mask shifted differently per dimension and axis
+    fn offset(&self, h: u64) -> f64 {
+        let mask = blue_noise_mask();
+        let ox = (h as usize) % BLUE_NOISE_SIZE;
+        let oy = ((h >> 32) as usize) % BLUE_NOISE_SIZE;
+        let x = (self.x as usize + ox) % BLUE_NOISE_SIZE;
+        let y = (self.y as usize + oy) % BLUE_NOISE_SIZE;
+        mask[y * BLUE_NOISE_SIZE + x]
+    }
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_pixel_sample(&mut self, x: u32, y: u32, sample_index: u32) {
+        self.x = x;
+        self.y = y;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.next_2d().0
+    }
+
+    fn next_2d(&mut self) -> (f64, f64) {
+        let dimension = u64::from(self.dimension);
+        self.dimension += 1;
+        let h = hash(&[self.seed, dimension]);
+        let (x, y) = scrambled_sobol_2d(self.sample_index, h);
+        let u = u32_to_unit(x) + self.offset(mix(h));
+        let v = u32_to_unit(y) + self.offset(mix(mix(h)));
+        (u.fract(), v.fract())
+    }
+}
+
+// A 64x64 tileable blue-noise threshold mask in [0, 1), made once with Ulichney's
+// void-and-cluster method
+fn blue_noise_mask() -> &'static [f64] {
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    MASK.get_or_init(|| void_and_cluster(BLUE_NOISE_SIZE))
+}
+
+fn void_and_cluster(size: usize) -> Vec<f64> {
+    let n = size * size;
+    let sigma = 1.5;
+
+    // Toroidal Gaussian energy contributed by a point at offset (dx, dy)
+    let kernel: Vec<f64> = (0..n)
+        .map(|i| {
+            let wrap = |d: usize| d.min(size - d) as f64;
+            let (dx, dy) = (wrap(i % size), wrap(i / size));
+            (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+
+    let mut on = vec![false; n];
+    let mut energy = vec![0.0; n];
+    let splat = |energy: &mut Vec<f64>, p: usize, sign: f64| {
+        let (px, py) = (p % size, p / size);
+        for (i, e) in energy.iter_mut().enumerate() {
+            let dx = (i % size + size - px) % size;
+            let dy = (i / size + size - py) % size;
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+    // Densest occupied spot, or emptiest free one
+    let tightest_cluster = |energy: &[f64], on: &[bool]| {
+        (0..n).filter(|&i| on[i]).max_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap()
+    };
+    let largest_void = |energy: &[f64], on: &[bool]| {
+        (0..n).filter(|&i| !on[i]).min_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap()
+    };
+
+    // Random initial pattern with a tenth of the points, relaxed until evenly spread
+    let mut random = IndependentSampler::new(0x5eed);
+    let initial = n / 10;
+    let mut placed = 0;
+    while placed < initial {
+        let p = ((random.next_1d() * n as f64) as usize).min(n - 1);
+        if !on[p] {
+            on[p] = true;
+            splat(&mut energy, p, 1.0);
+            placed += 1;
+        }
+    }
+    loop {
+        let cluster = tightest_cluster(&energy, &on);
+        on[cluster] = false;
+        splat(&mut energy, cluster, -1.0);
+        let void = largest_void(&energy, &on);
+        on[void] = true;
+        splat(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0usize; n];
+    let (initial_on, initial_energy) = (on.clone(), energy.clone());
+
+    // Ranks below the initial pattern come from removing clusters...
+    for r in (0..initial).rev() {
+        let cluster = tightest_cluster(&energy, &on);
+        on[cluster] = false;
+        splat(&mut energy, cluster, -1.0);
+        rank[cluster] = r;
+    }
+    // ...and ranks above it from filling voids
+    on = initial_on;
+    energy = initial_energy;
+    for r in initial..n {
+        let void = largest_void(&energy, &on);
+        on[void] = true;
+        splat(&mut energy, void, 1.0);
+        rank[void] = r;
+    }
+
+    rank.into_iter().map(|r| (r as f64 + 0.5) / n as f64).collect()
+}
+
+// Sampler selected for a render, created once per worker per tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SamplerKind {
+    #[default]
+    Independent,
+    Stratified { strata_per_axis: u32 },
+    Sobol,
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub fn create(self, seed: u64) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerKind::Stratified { strata_per_axis } => Box::new(StratifiedSampler::new(seed, strata_per_axis)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler::new(seed)),
+        }
+    }
+
+    // Sizes the strata so that `samples` per pixel cover them evenly
+    pub fn with_sample_count(self, samples: u32) -> Self {
+        match self {
+            SamplerKind::Stratified { .. } => {
+                SamplerKind::Stratified { strata_per_axis: ((samples as f64).sqrt() as u32).max(1) }
+            }
+            other => other,
+        }
+    }
+}
+
+impl FromStr for SamplerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(SamplerKind::Independent),
+            "stratified" => Ok(SamplerKind::Stratified { strata_per_axis: 4 }),
+            "sobol" => Ok(SamplerKind::Sobol),
+            "blue-noise" => Ok(SamplerKind::BlueNoise),
+            _ => Err(format!(
+                "unknown sampler '{}' (expected independent, stratified, sobol or blue-noise)",
+                s
+            )),
+        }
+    }
+}
+
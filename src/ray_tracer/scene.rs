@@ -0,0 +1,802 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use toml::Spanned;
+
+use crate::ray_tracer::hittable_list::HittableList;
+use crate::ray_tracer::light::{Light, LightList, PointLight, SpotLight, DirectionalLight, SphereLight, QuadLight};
+use crate::ray_tracer::quad::Quad;
+use crate::ray_tracer::plane::Plane;
+use crate::ray_tracer::disk::Disk;
+use crate::ray_tracer::cuboid::Cuboid;
+use crate::ray_tracer::cylinder::Cylinder;
+use crate::ray_tracer::cone::Cone;
+use crate::ray_tracer::torus::Torus;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::instance::Instance;
+use crate::ray_tracer::matrix::Matrix4;
+use crate::ray_tracer::aperture::{Aperture, ApertureImage};
+use crate::ray_tracer::environment::{Environment, EnvironmentLight, EnvironmentMap, GradientEnvironment, UniformEnvironment};
+use crate::ray_tracer::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight, MetallicRoughness};
+use crate::ray_tracer::texture::{CheckerTexture, ConstantTexture, ImageTexture, NoiseTexture, Texture};
+use crate::ray_tracer::sphere::Sphere;
+use crate::ray_tracer::triangle::Triangle;
+use crate::ray_tracer::obj_loader;
+use crate::ray_tracer::vec3::{Color, Point3, Vec3};
+
+pub struct CameraSettings {
+    pub position: Point3,
+    pub yaw: f64,
+    pub pitch: f64,
+    pub fov: f64,
+    // Lens radius; zero is a pinhole
+    pub aperture_radius: f64,
+    // Distance along the view direction that is in focus
+    pub focus_distance: f64,
+    pub aperture: Aperture,
+    pub autofocus: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            position: Point3::new(0.0, 0.0, 0.0),
+            yaw: -90.0, // Face negative Z direction initially
+            pitch: 0.0,
+            fov: 45.0,
+            aperture_radius: 0.0,
+            focus_distance: 10.0,
+            aperture: Aperture::default(),
+            autofocus: false,
+        }
+    }
+}
+
+pub struct Scene {
+    pub world: HittableList,
+    // Explicit lights; area lights also have their geometry in `world`
+    pub lights: LightList,
+    // What rays that leave the scene see
+    pub environment: Arc<dyn Environment>,
+    pub camera: CameraSettings,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    // The file is not valid TOML or does not match the expected layout. `field` is the
+    // key at fault when the error is inside an entry.
+    Parse { line: usize, column: usize, field: Option<String>, message: String },
+    // The file parsed but a value is out of range or refers to something undefined
+    Invalid { line: usize, field: String, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "{}", e),
+            SceneError::Parse { line, column, field: Some(field), message } => {
+                write!(f, "line {}, column {}: {}: {}", line, column, field, message)
+            }
+            SceneError::Parse { line, column, field: None, message } => {
+                write!(f, "line {}, column {}: {}", line, column, message)
+            }
+            SceneError::Invalid { line, field, message } => {
+                write!(f, "line {}: {}: {}", line, field, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl Scene {
+    // The built-in scene used when no scene file is given
+    pub fn default_scene() -> Self {
+        let mut world = HittableList::new();
+        let material_ground = Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.0)));
+        let material_center = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let material_left = Arc::new(Dielectric::new(1.5));
+        let material_bubble = Arc::new(Dielectric::new(1.0 / 1.5));
+        let material_right = Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.3));
+
+        world.add(Arc::new(Plane::new(Point3::new(0.0, -0.5, 0.0), Vec3::new(0.0, 1.0, 0.0), material_ground)));
+        world.add(Arc::new(Sphere::new(Point3::new(0.0, 0.0, -1.2), 0.5, material_center)));
+        world.add(Arc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, material_left)));
+        world.add(Arc::new(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.4, material_bubble)));
+        world.add(Arc::new(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, material_right)));
+
+        Self {
+            world,
+            lights: LightList::new(),
+            environment: Arc::new(GradientEnvironment::default()),
+            camera: CameraSettings::default(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let source = fs::read_to_string(path).map_err(SceneError::Io)?;
+        Self::parse(&source, path.parent().unwrap_or_else(|| Path::new("")))
+    }
+
+    // Relative asset paths in the scene (meshes) are resolved against base_dir
+    pub fn parse(source: &str, base_dir: &Path) -> Result<Self, SceneError> {
+        let desc: SceneDesc = toml::from_str(source).map_err(|e| {
+            let (line, column) = e
+                .span()
+                .map(|span| line_column(source, span.start))
+                .unwrap_or((1, 1));
+            SceneError::Parse { line, column, field: None, message: e.message().to_string() }
+        })?;
+        SceneBuilder { source, base_dir }.build(desc)
+    }
+}
+
+// 1-based line and column of a byte offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+// One table of the file with the position of each value, read before its layout is checked
+type RawTable = HashMap<String, Spanned<toml::Value>>;
+
+// Where an entry and each of its values start in the source
+struct Locations {
+    start: usize,
+    values: HashMap<String, usize>,
+}
+
+impl Locations {
+    // Offset of the value a field path such as `objects[2].radius` ends in, or of the
+    // entry when the key was left out
+    fn of(&self, field: &str) -> usize {
+        let key = field.rsplit('.').next().unwrap_or(field);
+        self.values.get(key).copied().unwrap_or(self.start)
+    }
+}
+
+// File layout. Everything is optional except the pieces each entry needs.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    camera: Option<Spanned<RawTable>>,
+    // Named textures that materials refer to in place of a color or value
+    #[serde(default)]
+    textures: HashMap<String, Spanned<RawTable>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<RawTable>>,
+    // Shapes by name, only placed in the world through `instance` objects
+    #[serde(default)]
+    geometry: HashMap<String, Spanned<RawTable>>,
+    #[serde(default)]
+    objects: Vec<Spanned<RawTable>>,
+    #[serde(default)]
+    lights: Vec<Spanned<RawTable>>,
+    #[serde(default)]
+    environment: Option<Spanned<RawTable>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    position: Option<[f64; 3]>,
+    yaw: Option<f64>,
+    pitch: Option<f64>,
+    fov: Option<f64>,
+    aperture: Option<f64>,
+    focus_distance: Option<f64>,
+    #[serde(default)]
+    autofocus: bool,
+    // Polygonal aperture; round when left out
+    blades: Option<u32>,
+    // Degrees
+    #[serde(default)]
+    blade_rotation: f64,
+    // Grayscale aperture mask, taking precedence over `blades`
+    aperture_image: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Constant { color: [f64; 3] },
+    // `scale` squares per unit of UV (or cubes per world unit); `space` is "uv" or "world"
+    Checker {
+        even: [f64; 3],
+        odd: [f64; 3],
+        #[serde(default = "one")] scale: f64,
+        #[serde(default = "default_checker_space")] space: String,
+    },
+    // PNG or JPEG (sRGB) or .hdr/.exr (linear); `wrap` is "repeat", "mirror" or "clamp"
+    Image { path: PathBuf, #[serde(default = "default_wrap")] wrap: String },
+    // Solid noise blended from `low` to `high`; `pattern` is "perlin", "fbm",
+    // "turbulence" or "marble" and `scale` the frequency
+    Noise {
+        #[serde(default = "default_noise_pattern")] pattern: String,
+        #[serde(default = "one")] scale: f64,
+        #[serde(default = "default_octaves")] octaves: u32,
+        #[serde(default)] seed: u64,
+        #[serde(default)] low: [f64; 3],
+        #[serde(default = "default_white")] high: [f64; 3],
+    },
+}
+
+// A color given inline or as the name of a texture
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorInput {
+    Color([f64; 3]),
+    Texture(String),
+}
+
+// A number given inline or as the name of a texture, read from its first channel
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScalarInput {
+    Value(f64),
+    Texture(String),
+}
+
+impl Default for ScalarInput {
+    fn default() -> Self {
+        ScalarInput::Value(0.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian { albedo: ColorInput },
+    Metal { albedo: ColorInput, #[serde(default)] fuzz: ScalarInput },
+    Dielectric { refraction_index: f64 },
+    Emissive { color: ColorInput, #[serde(default = "one")] intensity: f64 },
+    // glTF metallic-roughness. The factors default to 1 as in glTF and scale the
+    // `metallic_roughness` texture (roughness in green, metallic in blue) when there is one.
+    Pbr {
+        #[serde(default = "default_base_color")] base_color: ColorInput,
+        #[serde(default = "one")] metallic: f64,
+        #[serde(default = "one")] roughness: f64,
+        metallic_roughness: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere { center: [f64; 3], radius: f64, material: String },
+    Triangle { vertices: [[f64; 3]; 3], material: String },
+    Quad { corner: [f64; 3], u: [f64; 3], v: [f64; 3], material: String },
+    // Wavefront OBJ file; `material` is used for faces without an MTL material
+    Mesh { path: PathBuf, material: String },
+    Plane { point: [f64; 3], normal: [f64; 3], material: String },
+    Disk { center: [f64; 3], normal: [f64; 3], radius: f64, material: String },
+    // Turned about its center by `rotation`, degrees about x, then y, then z
+    Box { min: [f64; 3], max: [f64; 3], #[serde(default)] rotation: [f64; 3], material: String },
+    // `axis` runs from the center of the base to the top (to the apex for cones)
+    Cylinder { base: [f64; 3], axis: [f64; 3], radius: f64, material: String },
+    Cone { base: [f64; 3], axis: [f64; 3], radius: f64, material: String },
+    Torus {
+        center: [f64; 3],
+        #[serde(default = "default_up")] axis: [f64; 3],
+        major_radius: f64,
+        minor_radius: f64,
+        material: String,
+    },
+    // A shape from [geometry], scaled, then rotated (degrees about x, then y, then z),
+    // then moved by `translation`
+    Instance {
+        geometry: String,
+        #[serde(default)] translation: [f64; 3],
+        #[serde(default)] rotation: [f64; 3],
+        #[serde(default = "default_scale")] scale: [f64; 3],
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDesc {
+    Point { position: [f64; 3], color: [f64; 3], #[serde(default = "one")] intensity: f64 },
+    // Cone half-angles in degrees
+    Spot {
+        position: [f64; 3],
+        direction: [f64; 3],
+        color: [f64; 3],
+        #[serde(default = "one")] intensity: f64,
+        #[serde(default = "default_inner_angle")] inner_angle: f64,
+        #[serde(default = "default_outer_angle")] outer_angle: f64,
+    },
+    // `direction` is the way the light travels
+    Directional { direction: [f64; 3], color: [f64; 3], #[serde(default = "one")] intensity: f64 },
+    Sphere { center: [f64; 3], radius: f64, color: [f64; 3], #[serde(default = "one")] intensity: f64 },
+    Quad { corner: [f64; 3], u: [f64; 3], v: [f64; 3], color: [f64; 3], #[serde(default = "one")] intensity: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum EnvironmentDesc {
+    Gradient {
+        #[serde(default = "default_horizon")] horizon: [f64; 3],
+        #[serde(default = "default_zenith")] zenith: [f64; 3],
+        #[serde(default = "one")] intensity: f64,
+    },
+    Color { color: [f64; 3], #[serde(default = "one")] intensity: f64 },
+    // Equirectangular .hdr or .exr image; rotation is in degrees around the Y axis
+    Map { path: PathBuf, #[serde(default)] rotation: f64, #[serde(default = "one")] intensity: f64 },
+}
+
+fn default_horizon() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_zenith() -> [f64; 3] {
+    [0.5, 0.7, 1.0]
+}
+
+fn default_inner_angle() -> f64 {
+    20.0
+}
+
+fn default_outer_angle() -> f64 {
+    30.0
+}
+
+fn default_checker_space() -> String {
+    "uv".to_string()
+}
+
+fn default_wrap() -> String {
+    "repeat".to_string()
+}
+
+fn default_noise_pattern() -> String {
+    "fbm".to_string()
+}
+
+fn default_base_color() -> ColorInput {
+    ColorInput::Color([1.0, 1.0, 1.0])
+}
+
+fn default_octaves() -> u32 {
+    4
+}
+
+fn default_white() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_scale() -> [f64; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_up() -> [f64; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn vec3(v: [f64; 3]) -> Point3 {
+    Point3::new(v[0], v[1], v[2])
+}
+
+// Euler angles in degrees, applied about x first, then y, then z
+fn euler_rotation(degrees: [f64; 3]) -> Matrix4 {
+    Matrix4::rotation_z(degrees[2]) * Matrix4::rotation_y(degrees[1]) * Matrix4::rotation_x(degrees[0])
+}
+
+struct SceneBuilder<'a> {
+    source: &'a str,
+    base_dir: &'a Path,
+}
+
+impl SceneBuilder<'_> {
+    fn build(&self, desc: SceneDesc) -> Result<Scene, SceneError> {
+        let mut camera = CameraSettings::default();
+        if let Some(spanned) = desc.camera {
+            let (c, at) = self.entry::<CameraDesc>("camera", spanned)?;
+            if let Some(position) = c.position {
+                camera.position = vec3(position);
+            }
+            camera.yaw = c.yaw.unwrap_or(camera.yaw);
+            camera.pitch = c.pitch.unwrap_or(camera.pitch);
+            camera.fov = c.fov.unwrap_or(camera.fov);
+
+            if !(-89.0..=89.0).contains(&camera.pitch) {
+                return Err(self.invalid(&at, "camera.pitch", "must be between -89 and 89 degrees"));
+            }
+            if !(camera.fov > 0.0 && camera.fov < 180.0) {
+                return Err(self.invalid(&at, "camera.fov", "must be between 0 and 180 degrees"));
+            }
+
+            camera.aperture_radius = c.aperture.unwrap_or(camera.aperture_radius);
+            camera.focus_distance = c.focus_distance.unwrap_or(camera.focus_distance);
+            camera.autofocus = c.autofocus;
+            if camera.aperture_radius < 0.0 {
+                return Err(self.invalid(&at, "camera.aperture", "must not be negative"));
+            }
+            if camera.focus_distance <= 0.0 {
+                return Err(self.invalid(&at, "camera.focus_distance", "must be positive"));
+            }
+            if let Some(path) = c.aperture_image {
+                let image = ApertureImage::load(&self.base_dir.join(path))
+                    .map_err(|e| self.invalid(&at, "camera.aperture_image", &e.to_string()))?;
+                camera.aperture = Aperture::Image(Arc::new(image));
+            } else if let Some(blades) = c.blades {
+                if blades < 3 {
+                    return Err(self.invalid(&at, "camera.blades", "must be at least 3"));
+                }
+                camera.aperture = Aperture::Polygon { blades, rotation: c.blade_rotation };
+            }
+        }
+
+        let mut textures: HashMap<String, Arc<dyn Texture>> = HashMap::new();
+        for (name, spanned) in desc.textures {
+            let texture = self.texture(&name, spanned)?;
+            textures.insert(name, texture);
+        }
+
+        let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+        for (name, spanned) in desc.materials {
+            let material = self.material(&name, spanned, &textures)?;
+            materials.insert(name, material);
+        }
+
+        // Named shapes that `instance` objects place; each is built once and shared
+        let mut geometry: HashMap<String, Arc<dyn Hittable>> = HashMap::new();
+        for (name, spanned) in desc.geometry {
+            let object = self.object(&format!("geometry.{}", name), spanned, &materials, None)?;
+            geometry.insert(name, object);
+        }
+
+        let mut world = HittableList::new();
+        for (i, spanned) in desc.objects.into_iter().enumerate() {
+            world.add(self.object(&format!("objects[{}]", i), spanned, &materials, Some(&geometry))?);
+        }
+
+        let mut lights = LightList::new();
+        for (i, spanned) in desc.lights.into_iter().enumerate() {
+            let prefix = format!("lights[{}]", i);
+            let (light, at) = self.entry::<LightDesc>(&prefix, spanned)?;
+            let field = |f: &str| format!("{}.{}", prefix, f);
+            let positive = |value: f64, name: &str| {
+                if value > 0.0 { Ok(()) } else { Err(self.invalid(&at, &field(name), "must be positive")) }
+            };
+            let non_zero = |v: [f64; 3], name: &str| {
+                if vec3(v).near_zero() { Err(self.invalid(&at, &field(name), "must not be zero")) } else { Ok(()) }
+            };
+            let emission = |color: [f64; 3], intensity: f64| {
+                if intensity < 0.0 {
+                    Err(self.invalid(&at, &field("intensity"), "must not be negative"))
+                } else {
+                    Ok(vec3(color) * intensity)
+                }
+            };
+
+            let light: Arc<dyn Light> = match light {
+                LightDesc::Point { position, color, intensity } => {
+                    Arc::new(PointLight::new(vec3(position), emission(color, intensity)?))
+                }
+                LightDesc::Spot { position, direction, color, intensity, inner_angle, outer_angle } => {
+                    non_zero(direction, "direction")?;
+                    if !(0.0..=180.0).contains(&inner_angle) || !(0.0..=180.0).contains(&outer_angle) {
+                        return Err(self.invalid(&at, &field("outer_angle"), "angles must be between 0 and 180 degrees"));
+                    }
+                    let intensity = emission(color, intensity)?;
+                    Arc::new(SpotLight::new(vec3(position), vec3(direction), intensity, inner_angle, outer_angle))
+                }
+                LightDesc::Directional { direction, color, intensity } => {
+                    non_zero(direction, "direction")?;
+                    Arc::new(DirectionalLight::new(vec3(direction), emission(color, intensity)?))
+                }
+                LightDesc::Sphere { center, radius, color, intensity } => {
+                    positive(radius, "radius")?;
+                    Arc::new(SphereLight::new(vec3(center), radius, emission(color, intensity)?))
+                }
+                LightDesc::Quad { corner, u, v, color, intensity } => {
+                    if Vec3::cross(vec3(u), vec3(v)).near_zero() {
+                        return Err(self.invalid(&at, &field("u"), "u and v must not be parallel"));
+                    }
+                    Arc::new(QuadLight::new(vec3(corner), vec3(u), vec3(v), emission(color, intensity)?))
+                }
+            };
+
+            if let Some(geometry) = light.geometry() {
+                world.add(geometry);
+            }
+            lights.add(light);
+        }
+
+        let environment: Arc<dyn Environment> = match desc.environment {
+            Some(spanned) => {
+                let (environment, at) = self.entry::<EnvironmentDesc>("environment", spanned)?;
+                let intensity = |value: f64| {
+                    if value < 0.0 {
+                        Err(self.invalid(&at, "environment.intensity", "must not be negative"))
+                    } else {
+                        Ok(value)
+                    }
+                };
+                match environment {
+                    EnvironmentDesc::Gradient { horizon, zenith, intensity: scale } => {
+                        let scale = intensity(scale)?;
+                        Arc::new(GradientEnvironment::new(vec3(horizon) * scale, vec3(zenith) * scale))
+                    }
+                    EnvironmentDesc::Color { color, intensity: scale } => {
+                        Arc::new(UniformEnvironment::new(vec3(color) * intensity(scale)?))
+                    }
+                    EnvironmentDesc::Map { path, rotation, intensity: scale } => {
+                        let scale = intensity(scale)?;
+                        let map: Arc<dyn Environment> = Arc::new(
+                            EnvironmentMap::load(&self.base_dir.join(path), rotation, scale)
+                                .map_err(|e| self.invalid(&at, "environment.path", &e.to_string()))?,
+                        );
+                        // Bright spots in the map (the sun, windows) get sampled directly
+                        lights.add(Arc::new(EnvironmentLight::new(Arc::clone(&map))));
+                        map
+                    }
+                }
+            }
+            None => Arc::new(GradientEnvironment::default()),
+        };
+
+        Ok(Scene { world, lights, environment, camera })
+    }
+
+    // `geometry` is None while the named geometry itself is being built
+    fn object(
+        &self,
+        prefix: &str,
+        spanned: Spanned<RawTable>,
+        materials: &HashMap<String, Arc<dyn Material>>,
+        geometry: Option<&HashMap<String, Arc<dyn Hittable>>>,
+    ) -> Result<Arc<dyn Hittable>, SceneError> {
+        let (desc, at) = self.entry::<ObjectDesc>(prefix, spanned)?;
+        let field = |f: &str| format!("{}.{}", prefix, f);
+        let lookup = |name: &str| {
+            materials.get(name).cloned().ok_or_else(|| {
+                self.invalid(&at, &field("material"), &format!("unknown material '{}'", name))
+            })
+        };
+        let positive = |value: f64, name: &str| {
+            if value > 0.0 { Ok(()) } else { Err(self.invalid(&at, &field(name), "must be positive")) }
+        };
+        let non_zero = |v: [f64; 3], name: &str| {
+            if vec3(v).near_zero() { Err(self.invalid(&at, &field(name), "must not be zero")) } else { Ok(()) }
+        };
+
+        Ok(match desc {
+            ObjectDesc::Sphere { center, radius, material } => {
+                if radius <= 0.0 {
+                    return Err(self.invalid(&at, &field("radius"), "must be positive"));
+                }
+                Arc::new(Sphere::new(vec3(center), radius, lookup(&material)?))
+            }
+            ObjectDesc::Triangle { vertices, material } => {
+                let [v0, v1, v2] = vertices.map(vec3);
+                if Vec3::cross(v1 - v0, v2 - v0).near_zero() {
+                    return Err(self.invalid(&at, &field("vertices"), "triangle is degenerate"));
+                }
+                Arc::new(Triangle::new(v0, v1, v2, lookup(&material)?))
+            }
+            ObjectDesc::Quad { corner, u, v, material } => {
+                if Vec3::cross(vec3(u), vec3(v)).near_zero() {
+                    return Err(self.invalid(&at, &field("u"), "u and v must not be parallel"));
+                }
+                Arc::new(Quad::new(vec3(corner), vec3(u), vec3(v), lookup(&material)?))
+            }
+            ObjectDesc::Mesh { path, material } => {
+                let mesh = obj_loader::load_obj(&self.base_dir.join(path), lookup(&material)?)
+                    .map_err(|e| self.invalid(&at, &field("path"), &e.to_string()))?;
+                Arc::new(mesh)
+            }
+            ObjectDesc::Plane { point, normal, material } => {
+                non_zero(normal, "normal")?;
+                Arc::new(Plane::new(vec3(point), vec3(normal), lookup(&material)?))
+            }
+            ObjectDesc::Disk { center, normal, radius, material } => {
+                non_zero(normal, "normal")?;
+                positive(radius, "radius")?;
+                Arc::new(Disk::new(vec3(center), vec3(normal), radius, lookup(&material)?))
+            }
+            ObjectDesc::Box { min, max, rotation, material } => {
+                if (0..3).any(|i| max[i] <= min[i]) {
+                    return Err(self.invalid(&at, &field("max"), "must be greater than min on every axis"));
+                }
+                let (min, max) = (vec3(min), vec3(max));
+                let half_size = [(max.x() - min.x()) / 2.0, (max.y() - min.y()) / 2.0, (max.z() - min.z()) / 2.0];
+                let rotation = euler_rotation(rotation);
+                let axes = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)]
+                    .map(|axis| rotation.transform_vector(axis));
+                Arc::new(Cuboid::oriented((min + max) / 2.0, half_size, axes, lookup(&material)?))
+            }
+            ObjectDesc::Cylinder { base, axis, radius, material } => {
+                non_zero(axis, "axis")?;
+                positive(radius, "radius")?;
+                Arc::new(Cylinder::new(vec3(base), vec3(axis), radius, lookup(&material)?))
+            }
+            ObjectDesc::Cone { base, axis, radius, material } => {
+                non_zero(axis, "axis")?;
+                positive(radius, "radius")?;
+                Arc::new(Cone::new(vec3(base), vec3(axis), radius, lookup(&material)?))
+            }
+            ObjectDesc::Torus { center, axis, major_radius, minor_radius, material } => {
+                non_zero(axis, "axis")?;
+                positive(major_radius, "major_radius")?;
+                positive(minor_radius, "minor_radius")?;
+                if minor_radius >= major_radius {
+                    return Err(self.invalid(&at, &field("minor_radius"), "must be smaller than major_radius"));
+                }
+                let material = lookup(&material)?;
+                Arc::new(Torus::new(vec3(center), vec3(axis), major_radius, minor_radius, material))
+            }
+            ObjectDesc::Instance { geometry: name, translation, rotation, scale } => {
+                let Some(geometry) = geometry else {
+                    return Err(self.invalid(&at, &field("type"), "instances cannot be used as geometry"));
+                };
+                let object = geometry.get(&name).cloned().ok_or_else(|| {
+                    self.invalid(&at, &field("geometry"), &format!("unknown geometry '{}'", name))
+                })?;
+                if scale.contains(&0.0) {
+                    return Err(self.invalid(&at, &field("scale"), "must not be zero on any axis"));
+                }
+                let transform = Matrix4::translation(vec3(translation))
+                    * euler_rotation(rotation)
+                    * Matrix4::scaling(vec3(scale));
+                let instance = Instance::new(object, transform)
+                    .ok_or_else(|| self.invalid(&at, &field("scale"), "transform is not invertible"))?;
+                Arc::new(instance)
+            }
+        })
+    }
+
+    fn texture(&self, name: &str, spanned: Spanned<RawTable>) -> Result<Arc<dyn Texture>, SceneError> {
+        let prefix = format!("textures.{}", name);
+        let (desc, at) = self.entry::<TextureDesc>(&prefix, spanned)?;
+        let field = |f: &str| format!("{}.{}", prefix, f);
+        let positive = |value: f64, name: &str| {
+            if value > 0.0 { Ok(()) } else { Err(self.invalid(&at, &field(name), "must be positive")) }
+        };
+
+        Ok(match desc {
+            TextureDesc::Constant { color } => Arc::new(ConstantTexture::new(vec3(color))),
+            TextureDesc::Checker { even, odd, scale, space } => {
+                positive(scale, "scale")?;
+                let space = space.parse().map_err(|e: String| self.invalid(&at, &field("space"), &e))?;
+                let even = Arc::new(ConstantTexture::new(vec3(even)));
+                let odd = Arc::new(ConstantTexture::new(vec3(odd)));
+                Arc::new(CheckerTexture::new(even, odd, scale, space))
+            }
+            TextureDesc::Image { path, wrap } => {
+                let wrap = wrap.parse().map_err(|e: String| self.invalid(&at, &field("wrap"), &e))?;
+                let image = ImageTexture::load(&self.base_dir.join(path), wrap)
+                    .map_err(|e| self.invalid(&at, &field("path"), &e.to_string()))?;
+                Arc::new(image)
+            }
+            TextureDesc::Noise { pattern, scale, octaves, seed, low, high } => {
+                let pattern = pattern.parse().map_err(|e: String| self.invalid(&at, &field("pattern"), &e))?;
+                positive(scale, "scale")?;
+                if !(1..=16).contains(&octaves) {
+                    return Err(self.invalid(&at, &field("octaves"), "must be between 1 and 16"));
+                }
+                Arc::new(NoiseTexture::new(pattern, scale, octaves, vec3(low), vec3(high), seed))
+            }
+        })
+    }
+
+    fn material(
+        &self,
+        name: &str,
+        spanned: Spanned<RawTable>,
+        textures: &HashMap<String, Arc<dyn Texture>>,
+    ) -> Result<Arc<dyn Material>, SceneError> {
+        let prefix = format!("materials.{}", name);
+        let (desc, at) = self.entry::<MaterialDesc>(&prefix, spanned)?;
+        let field = |f: &str| format!("{}.{}", prefix, f);
+        let lookup = |texture: &str, name: &str| {
+            textures.get(texture).cloned().ok_or_else(|| {
+                self.invalid(&at, &field(name), &format!("unknown texture '{}'", texture))
+            })
+        };
+        let color = |input: ColorInput, name: &str| -> Result<Arc<dyn Texture>, SceneError> {
+            match input {
+                ColorInput::Color(c) => Ok(Arc::new(ConstantTexture::new(vec3(c)))),
+                ColorInput::Texture(texture) => lookup(&texture, name),
+            }
+        };
+
+        Ok(match desc {
+            MaterialDesc::Lambertian { albedo } => Arc::new(Lambertian::textured(color(albedo, "albedo")?)),
+            MaterialDesc::Metal { albedo, fuzz } => {
+                let fuzz: Arc<dyn Texture> = match fuzz {
+                    ScalarInput::Value(fuzz) => {
+                        if !(0.0..=1.0).contains(&fuzz) {
+                            return Err(self.invalid(&at, &field("fuzz"), "must be between 0 and 1"));
+                        }
+                        Arc::new(ConstantTexture::new(Color::new(fuzz, fuzz, fuzz)))
+                    }
+                    ScalarInput::Texture(texture) => lookup(&texture, "fuzz")?,
+                };
+                Arc::new(Metal::textured(color(albedo, "albedo")?, fuzz))
+            }
+            MaterialDesc::Dielectric { refraction_index } => {
+                if refraction_index <= 0.0 {
+                    return Err(self.invalid(&at, &field("refraction_index"), "must be positive"));
+                }
+                Arc::new(Dielectric::new(refraction_index))
+            }
+            MaterialDesc::Emissive { color: emit, intensity } => {
+                if intensity < 0.0 {
+                    return Err(self.invalid(&at, &field("intensity"), "must not be negative"));
+                }
+                Arc::new(DiffuseLight::textured(color(emit, "color")?, intensity))
+            }
+            MaterialDesc::Pbr { base_color, metallic, roughness, metallic_roughness } => {
+                for (value, name) in [(metallic, "metallic"), (roughness, "roughness")] {
+                    if !(0.0..=1.0).contains(&value) {
+                        return Err(self.invalid(&at, &field(name), "must be between 0 and 1"));
+                    }
+                }
+                let packed = match metallic_roughness {
+                    Some(texture) => Some(lookup(&texture, "metallic_roughness")?),
+                    None => None,
+                };
+                let base_color = color(base_color, "base_color")?;
+                Arc::new(MetallicRoughness::textured(base_color, metallic, roughness, packed))
+            }
+        })
+    }
+
+    // Reads one table of the file as a `T`. Errors name the key at fault and point at the
+    // line of its value; serde names unknown and missing keys itself, and for anything else
+    // (a value of the wrong type, an unknown `type`) the key is found by leaving keys out
+    // one at a time until the error changes.
+    fn entry<T: DeserializeOwned>(&self, prefix: &str, spanned: Spanned<RawTable>) -> Result<(T, Locations), SceneError> {
+        let start = spanned.span().start;
+        let raw = spanned.into_inner();
+        let at = Locations { start, values: raw.iter().map(|(key, value)| (key.clone(), value.span().start)).collect() };
+        let table: toml::Table = raw.into_iter().map(|(key, value)| (key, value.into_inner())).collect();
+
+        let error = match T::deserialize(toml::Value::Table(table.clone())) {
+            Ok(desc) => return Ok((desc, at)),
+            Err(e) => e.message().to_string(),
+        };
+        let named = ["unknown field `", "missing field `"]
+            .iter()
+            .find_map(|pattern| error.strip_prefix(pattern))
+            .and_then(|rest| rest.split('`').next())
+            .map(str::to_string);
+        let key = named.or_else(|| {
+            // The tag last, since leaving it out changes every error
+            let mut keys: Vec<&String> = table.keys().collect();
+            keys.sort_by_key(|key| *key == "type");
+            keys.into_iter()
+                .find(|key| {
+                    let mut rest = table.clone();
+                    rest.remove(*key);
+                    T::deserialize(toml::Value::Table(rest)).map_or_else(|e| e.message() != error, |_| true)
+                })
+                .cloned()
+        });
+
+        let field = key.map_or_else(|| prefix.to_string(), |key| format!("{}.{}", prefix, key));
+        let (line, column) = line_column(self.source, at.of(&field));
+        Err(SceneError::Parse { line, column, field: Some(field), message: error })
+    }
+
+    fn invalid(&self, at: &Locations, field: &str, message: &str) -> SceneError {
+        let line = line_column(self.source, at.of(field)).0;
+        SceneError::Invalid { line, field: field.to_string(), message: message.to_string() }
+    }
+}
@@ -1,21 +1,27 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
 use crate::ray_tracer::hittable::Hittable;
 use crate::ray_tracer::ray::Ray;
 use crate::ray_tracer::hit_record::HitRecord;
 use crate::ray_tracer::interval::Interval;
 use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
 
 pub struct Sphere {
     center: Point3,
     radius: f64,
     radius_squared: f64,
+    material: Arc<dyn Material>,
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64) -> Self {
+    pub fn new(center: Point3, radius: f64, material: Arc<dyn Material>) -> Self {
         Sphere { 
             center, 
             radius, 
-            radius_squared: radius * radius 
+            radius_squared: radius * radius,
+            material,
         }
     }
 }
@@ -45,7 +51,25 @@ impl Hittable for Sphere {
         
         rec.t = root;
         rec.p = r.at(rec.t);
-        rec.normal = (rec.p - self.center) / self.radius;
+        let outward_normal = (rec.p - self.center) / self.radius;
+        rec.set_outward_normal(r, outward_normal);
+        (rec.u, rec.v) = sphere_uv(outward_normal);
+        // Along the line of latitude, the way u grows
+        rec.set_tangent(Vec3::new(outward_normal.z(), 0.0, -outward_normal.x()));
+        rec.material = Some(Arc::clone(&self.material));
         true
     }
-}
\ No newline at end of file
+
+    fn bounding_box(&self) -> Aabb {
+        let rvec = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::from_points(self.center - rvec, self.center + rvec)
+    }
+}
+
+// Longitude and latitude of a point on the unit sphere, both in [0, 1]; v runs from the
+// bottom (-Y) to the top and u starts at -X going around through +Z
+pub fn sphere_uv(p: Vec3) -> (f64, f64) {
+    let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
@@ -0,0 +1,141 @@
+use crate::ray_tracer::camera::{Camera, Viewport};
+use crate::ray_tracer::pixel_data::PixelData;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::vec3::{Color, Vec3};
+
+// History counts for at most this many samples, so the image keeps following the new
+// view while the camera moves and reprojection blur does not build up
+pub const MAX_HISTORY: u32 = 16;
+
+// History may lie this many standard deviations outside the new samples around a pixel
+const CLAMP_SIGMA: f64 = 1.0;
+// Relative difference in distance from the camera still taken as the same surface
+const DEPTH_TOLERANCE: f64 = 0.05;
+// Smallest cosine between normals still taken as the same surface
+const NORMAL_TOLERANCE: f64 = 0.9;
+
+// The accumulation buffer from before the camera moved, and the view it was rendered from
+pub struct History {
+    viewport: Viewport,
+    width: u32,
+    height: u32,
+    pixels: Vec<PixelData>,
+}
+
+// Accumulated samples of the previous view that landed on one pixel of the new view
+pub struct HistorySample {
+    pub mean: Color,
+    // Population variance of the samples
+    pub variance: Color,
+    pub sample_count: u32,
+}
+
+impl History {
+    pub fn new(camera: &Camera, width: u32, height: u32, pixels: Vec<PixelData>) -> Self {
+        Self { viewport: camera.viewport(), width, height, pixels }
+    }
+
+    // Hands the buffer back so it can be reused
+    pub fn into_pixels(self) -> Vec<PixelData> {
+        self.pixels
+    }
+
+    // Looks up where the first hit of `ray`, as recorded in `data`, was in the previous
+    // view. The 2x2 pixels around that spot are blended bilinearly, skipping those that saw
+    // a different surface. None if the point was off screen or hidden before (disoccluded).
+    pub fn reproject(&self, ray: &Ray, data: &PixelData) -> Option<HistorySample> {
+        let point = ray.at(data.depth as f64);
+        // Misses are at infinity, where only the direction matters
+        let direction = if data.depth.is_finite() { point - self.viewport.origin() } else { ray.direction() };
+        let (s, t) = self.viewport.project(direction)?;
+
+        // Continuous pixel coordinates, pixel centers at .5
+        let x = s * self.width as f64 - 0.5;
+        let y = (1.0 - t) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let taps = [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ];
+
+        let mut mean = Color::new(0.0, 0.0, 0.0);
+        let mut second_moment = Color::new(0.0, 0.0, 0.0);
+        let mut sample_count = 0.0;
+        let mut total = 0.0;
+        for (dx, dy, w) in taps {
+            let (px, py) = (x0 as i64 + dx, y0 as i64 + dy);
+            if w <= 0.0 || px < 0 || py < 0 || px >= self.width as i64 || py >= self.height as i64 {
+                continue;
+            }
+            let previous = &self.pixels[(py as u32 * self.width + px as u32) as usize];
+            if previous.sample_count == 0 || !self.same_surface(point, data, px as u32, py as u32, previous) {
+                continue;
+            }
+            let n = previous.sample_count as f64;
+            mean += previous.color * w;
+            second_moment += (previous.m2 / n + previous.color * previous.color) * w;
+            sample_count += n * w;
+            total += w;
+        }
+
+        if total < 1e-3 {
+            return None;
+        }
+        let mean = mean / total;
+        Some(HistorySample {
+            mean,
+            variance: second_moment / total - mean * mean,
+            sample_count: ((sample_count / total).round() as u32).clamp(1, MAX_HISTORY),
+        })
+    }
+
+    fn same_surface(&self, point: Vec3, current: &PixelData, x: u32, y: u32, previous: &PixelData) -> bool {
+        match (current.depth.is_finite(), previous.depth.is_finite()) {
+            (false, false) => true,
+            (true, true) => {
+                let s = (x as f64 + 0.5) / self.width as f64;
+                let t = ((self.height - 1 - y) as f64 + 0.5) / self.height as f64;
+                let previous_point = self.viewport.get_ray(s, t).at(previous.depth as f64);
+
+                let origin = self.viewport.origin();
+                let expected = (point - origin).length();
+                let found = (previous_point - origin).length();
+                (expected - found).abs() <= DEPTH_TOLERANCE * expected
+                    && Vec3::dot(current.normal, previous.normal) >= NORMAL_TOLERANCE
+            }
+            _ => false,
+        }
+    }
+}
+
+impl HistorySample {
+    // Adds the history to a pixel holding its first sample of the new view. The history
+    // mean is first clamped to the spread of `neighborhood`, the new samples around the
+    // pixel, so lighting that changed with the view cannot linger as ghosts.
+    pub fn merge_into(self, pixel: &mut PixelData, neighborhood: &[Color]) {
+        let mean = clamp_to_neighborhood(self.mean, neighborhood);
+        let variance = Color::new(self.variance.x().max(0.0), self.variance.y().max(0.0), self.variance.z().max(0.0));
+        pixel.merge(mean, variance * self.sample_count as f64, self.sample_count);
+    }
+}
+
+fn clamp_to_neighborhood(color: Color, neighborhood: &[Color]) -> Color {
+    if neighborhood.is_empty() {
+        return color;
+    }
+    let n = neighborhood.len() as f64;
+    let mean = neighborhood.iter().fold(Color::new(0.0, 0.0, 0.0), |sum, &c| sum + c) / n;
+    let second_moment = neighborhood.iter().fold(Color::new(0.0, 0.0, 0.0), |sum, &c| sum + c * c) / n;
+    let clamp = |value: f64, mean: f64, second_moment: f64| {
+        let sigma = (second_moment - mean * mean).max(0.0).sqrt();
+        value.clamp(mean - CLAMP_SIGMA * sigma, mean + CLAMP_SIGMA * sigma)
+    };
+    Color::new(
+        clamp(color.x(), mean.x(), second_moment.x()),
+        clamp(color.y(), mean.y(), second_moment.y()),
+        clamp(color.z(), mean.z(), second_moment.z()),
+    )
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use image::ColorType;
+use crate::ray_tracer::perlin::Perlin;
+use crate::ray_tracer::tonemap::srgb_to_linear;
+use crate::ray_tracer::vec3::{Color, Point3};
+
+// A color that varies over a surface. Single-valued textures such as roughness read the
+// first channel.
+pub trait Texture: Send + Sync {
+    // Color at surface coordinates (u, v) of the world point p
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+}
+
+pub struct ConstantTexture {
+    color: Color,
+}
+
+impl ConstantTexture {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for ConstantTexture {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color {
+        self.color
+    }
+}
+
+// Coordinates a checker pattern is laid out in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CheckerSpace {
+    // Squares across the surface's UVs
+    #[default]
+    Uv,
+    // Cubes in world space, for shapes whose UVs don't suit a checker
+    World,
+}
+
+impl FromStr for CheckerSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uv" => Ok(CheckerSpace::Uv),
+            "world" => Ok(CheckerSpace::World),
+            _ => Err(format!("unknown checker space '{}' (expected uv or world)", s)),
+        }
+    }
+}
+
+// Alternates between two textures, `scale` squares per unit
+pub struct CheckerTexture {
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+    scale: f64,
+    space: CheckerSpace,
+}
+
+impl CheckerTexture {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f64, space: CheckerSpace) -> Self {
+        Self { even, odd, scale, space }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color {
+        let cells = match self.space {
+            CheckerSpace::Uv => (u * self.scale).floor() + (v * self.scale).floor(),
+            CheckerSpace::World => {
+                (p.x() * self.scale).floor() + (p.y() * self.scale).floor() + (p.z() * self.scale).floor()
+            }
+        };
+        if (cells as i64).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+// What an image texture does with UVs outside [0, 1]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    // Every other tile flipped, so the edges always meet
+    Mirror,
+    // The edge texels stretch outwards
+    Clamp,
+}
+
+impl WrapMode {
+    // Texel index along an axis of n texels
+    #[inline]
+    fn apply(self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m < n { m } else { 2 * n - 1 - m }
+            }
+            WrapMode::Clamp => i.clamp(0, n - 1),
+        };
+        i as usize
+    }
+}
+
+impl FromStr for WrapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repeat" => Ok(WrapMode::Repeat),
+            "mirror" => Ok(WrapMode::Mirror),
+            "clamp" => Ok(WrapMode::Clamp),
+            _ => Err(format!("unknown wrap mode '{}' (expected repeat, mirror or clamp)", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TextureError(String);
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for TextureError {}
+
+// Bilinearly filtered image with v = 0 at the bottom row
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // Linear RGB, row by row from the top
+    pixels: Vec<Color>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    // PNG and JPEG images are taken to be sRGB encoded and linearized; .hdr and .exr
+    // files are already linear
+    pub fn load(path: &Path, wrap: WrapMode) -> Result<Self, TextureError> {
+        let image = image::open(path).map_err(|e| TextureError(format!("{}: {}", path.display(), e)))?;
+        let linear = matches!(image.color(), ColorType::Rgb32F | ColorType::Rgba32F);
+        let image = image.into_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width == 0 || height == 0 {
+            return Err(TextureError(format!("{}: image is empty", path.display())));
+        }
+        let decode = |c: f32| if linear { c as f64 } else { srgb_to_linear(c as f64) };
+        let pixels = image.pixels().map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2]))).collect();
+        Ok(Self::from_pixels(width, height, pixels, wrap))
+    }
+
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>, wrap: WrapMode) -> Self {
+        assert_eq!(pixels.len(), width * height);
+        Self { width, height, pixels, wrap }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        // Texel centers sit at half-integer coordinates
+        let x = u * self.width as f64 - 0.5;
+        let y = (1.0 - v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let texel = |i: i64, j: i64| {
+            let i = self.wrap.apply(i, self.width);
+            let j = self.wrap.apply(j, self.height);
+            self.pixels[j * self.width + i]
+        };
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+// How a noise texture turns Perlin noise into a blend factor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NoisePattern {
+    // A single octave
+    Perlin,
+    // Several octaves summed, like clouds
+    #[default]
+    Fbm,
+    // Summed absolute octaves, with sharp creases
+    Turbulence,
+    // Sine bands along z, distorted by turbulence
+    Marble,
+}
+
+impl FromStr for NoisePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "perlin" => Ok(NoisePattern::Perlin),
+            "fbm" => Ok(NoisePattern::Fbm),
+            "turbulence" => Ok(NoisePattern::Turbulence),
+            "marble" => Ok(NoisePattern::Marble),
+            _ => Err(format!(
+                "unknown noise pattern '{}' (expected perlin, fbm, turbulence or marble)",
+                s
+            )),
+        }
+    }
+}
+
+// Solid noise in world space blended between two colors. `scale` is the noise frequency.
+pub struct NoiseTexture {
+    perlin: Perlin,
+    pattern: NoisePattern,
+    scale: f64,
+    octaves: u32,
+    low: Color,
+    high: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(pattern: NoisePattern, scale: f64, octaves: u32, low: Color, high: Color, seed: u64) -> Self {
+        Self { perlin: Perlin::new(seed), pattern, scale, octaves, low, high }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color {
+        let q = p * self.scale;
+        let t = match self.pattern {
+            NoisePattern::Perlin => 0.5 * (1.0 + self.perlin.noise(q)),
+            NoisePattern::Fbm => 0.5 * (1.0 + self.perlin.fbm(q, self.octaves)),
+            NoisePattern::Turbulence => self.perlin.turbulence(q, self.octaves),
+            NoisePattern::Marble => 0.5 * (1.0 + (q.z() + 10.0 * self.perlin.turbulence(q, self.octaves)).sin()),
+        };
+        let t = t.clamp(0.0, 1.0);
+        self.low * (1.0 - t) + self.high * t
+    }
+}
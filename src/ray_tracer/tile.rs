@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+// A rectangle of pixels rendered as one unit of work
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Order in which tiles of a frame are handed to the worker threads
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TileOrder {
+    // Row by row from the top left
+    Scanline,
+    // Outwards from the center, so the middle of the image resolves first
+    #[default]
+    Spiral,
+    // Along a Hilbert curve, keeping consecutive tiles next to each other
+    Hilbert,
+}
+
+impl FromStr for TileOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scanline" => Ok(TileOrder::Scanline),
+            "spiral" => Ok(TileOrder::Spiral),
+            "hilbert" => Ok(TileOrder::Hilbert),
+            _ => Err(format!("unknown tile order '{}' (expected scanline, spiral or hilbert)", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileSettings {
+    // Edge length in pixels; tiles on the right and bottom border may be smaller
+    pub size: u32,
+    pub order: TileOrder,
+}
+
+impl Default for TileSettings {
+    fn default() -> Self {
+        Self { size: 32, order: TileOrder::default() }
+    }
+}
+
+// Reported after each finished tile
+#[derive(Clone, Copy, Debug)]
+pub struct TileProgress {
+    pub tile: Tile,
+    pub completed: usize,
+    pub total: usize,
+}
+
+// Splits an image into tiles, listed in the requested order
+pub fn tiles(image_width: u32, image_height: u32, settings: TileSettings) -> Vec<Tile> {
+    let size = settings.size.max(1);
+    let columns = image_width.div_ceil(size);
+    let rows = image_height.div_ceil(size);
+
+    let mut grid: Vec<(u32, u32)> = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect();
+
+    match settings.order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            // Ring by ring around the center tile, each ring walked by angle
+            let center_x = (columns as f64 - 1.0) / 2.0;
+            let center_y = (rows as f64 - 1.0) / 2.0;
+            let key = |&(column, row): &(u32, u32)| {
+                let dx = column as f64 - center_x;
+                let dy = row as f64 - center_y;
+                let ring = dx.abs().max(dy.abs()).round();
+                (ring, dy.atan2(dx))
+            };
+            grid.sort_by(|a, b| {
+                let (ring_a, angle_a) = key(a);
+                let (ring_b, angle_b) = key(b);
+                ring_a.total_cmp(&ring_b).then(angle_a.total_cmp(&angle_b))
+            });
+        }
+        TileOrder::Hilbert => {
+            let side = columns.max(rows).next_power_of_two();
+            grid.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+        }
+    }
+
+    grid.into_iter()
+        .map(|(column, row)| {
+            let x = column * size;
+            let y = row * size;
+            Tile { x, y, width: size.min(image_width - x), height: size.min(image_height - y) }
+        })
+        .collect()
+}
+
+// Distance of (x, y) along the Hilbert curve filling a side x side grid (side a power of two)
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0u64;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = u32::from(x & s > 0);
+        let ry = u32::from(y & s > 0);
+        index += u64::from(s) * u64::from(s) * u64::from((3 * rx) ^ ry);
+        // Rotate the quadrant so the curve stays continuous
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
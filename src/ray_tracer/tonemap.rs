@@ -0,0 +1,171 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ray_tracer::environment::luminance;
+use crate::ray_tracer::vec3::Color;
+
+// Curve that maps scene-referred radiance to the [0, 1] display range
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ToneMapper {
+    // Clamp everything above 1
+    #[default]
+    Linear,
+    // L / (1 + L) on luminance; never reaches white
+    Reinhard,
+    // Reinhard with luminance `white_point` mapped to 1
+    ExtendedReinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Sobotka's AgX with the default look, via Wrensch's polynomial fit
+    Agx,
+}
+
+impl ToneMapper {
+    pub const ALL: [ToneMapper; 5] = [
+        ToneMapper::Linear,
+        ToneMapper::Reinhard,
+        ToneMapper::ExtendedReinhard,
+        ToneMapper::Aces,
+        ToneMapper::Agx,
+    ];
+
+    // The following operator, wrapping around; used to cycle through them in the window
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for ToneMapper {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(ToneMapper::Linear),
+            "reinhard" => Ok(ToneMapper::Reinhard),
+            "reinhard-extended" => Ok(ToneMapper::ExtendedReinhard),
+            "aces" => Ok(ToneMapper::Aces),
+            "agx" => Ok(ToneMapper::Agx),
+            _ => Err(format!(
+                "unknown tone mapper '{}' (expected linear, reinhard, reinhard-extended, aces or agx)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ToneMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ToneMapper::Linear => "linear",
+            ToneMapper::Reinhard => "reinhard",
+            ToneMapper::ExtendedReinhard => "reinhard-extended",
+            ToneMapper::Aces => "aces",
+            ToneMapper::Agx => "agx",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Turns accumulated radiance into display pixels: exposure, then the tone curve, then the
+// sRGB transfer function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapping {
+    // In stops; each +1 doubles the brightness
+    pub exposure: f64,
+    pub operator: ToneMapper,
+    // Luminance that becomes white under the extended Reinhard operator
+    pub white_point: f64,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self { exposure: 0.0, operator: ToneMapper::default(), white_point: 4.0 }
+    }
+}
+
+impl ToneMapping {
+    // Display-referred linear color in [0, 1]
+    pub fn apply(&self, color: Color) -> Color {
+        let c = color * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneMapper::Linear => c,
+            ToneMapper::Reinhard => scale_luminance(c, |l| l / (1.0 + l)),
+            ToneMapper::ExtendedReinhard => {
+                let white_squared = self.white_point * self.white_point;
+                scale_luminance(c, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+            }
+            ToneMapper::Aces => Color::new(aces(c.x()), aces(c.y()), aces(c.z())),
+            ToneMapper::Agx => agx(c),
+        };
+        Color::new(mapped.x().clamp(0.0, 1.0), mapped.y().clamp(0.0, 1.0), mapped.z().clamp(0.0, 1.0))
+    }
+
+    pub fn to_rgba8(&self, color: Color) -> [u8; 4] {
+        let c = self.apply(color);
+        let encode = |v: f64| (linear_to_srgb(v) * 255.0 + 0.5) as u8;
+        [encode(c.x()), encode(c.y()), encode(c.z()), 255]
+    }
+}
+
+// Rescales the color so its luminance follows `curve`, keeping the hue
+fn scale_luminance(c: Color, curve: impl Fn(f64) -> f64) -> Color {
+    let l = luminance(c);
+    if l <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    c * (curve(l) / l)
+}
+
+fn aces(x: f64) -> f64 {
+    // The fit expects the exposure of the reference transform, hence the 0.6
+    let x = x.max(0.0) * 0.6;
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+fn agx(c: Color) -> Color {
+    const MIN_EV: f64 = -12.47393;
+    const MAX_EV: f64 = 4.026069;
+
+    // Into the AgX working space, where the curve is applied per channel on log2 values
+    let inset = Color::new(
+        0.842479062253094 * c.x() + 0.0784335999999992 * c.y() + 0.0792237451477643 * c.z(),
+        0.0423282422610123 * c.x() + 0.878468636469772 * c.y() + 0.0791661274605434 * c.z(),
+        0.0423756549057051 * c.x() + 0.0784336 * c.y() + 0.879142973793104 * c.z(),
+    );
+    let curve = |v: f64| {
+        let log = v.max(1e-10).log2().clamp(MIN_EV, MAX_EV);
+        let x = (log - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232
+    };
+    let v = Color::new(curve(inset.x()), curve(inset.y()), curve(inset.z()));
+
+    // Back out of the working space; the curve's output is display encoded with gamma 2.2
+    let outset = Color::new(
+        1.19687900512017 * v.x() - 0.0980208811401368 * v.y() - 0.0990297440797205 * v.z(),
+        -0.0528968517574562 * v.x() + 1.15190312990417 * v.y() - 0.0989611768448433 * v.z(),
+        -0.0529716355144438 * v.x() - 0.0980434501171241 * v.y() + 1.15107367264116 * v.z(),
+    );
+    let linear = |v: f64| v.max(0.0).powf(2.2);
+    Color::new(linear(outset.x()), linear(outset.y()), linear(outset.z()))
+}
+
+// The sRGB opto-electronic transfer function for a value in [0, 1]
+pub fn linear_to_srgb(v: f64) -> f64 {
+    if v <= 0.0031308 {
+        12.92 * v
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Inverse of `linear_to_srgb`, for decoding 8-bit images
+pub fn srgb_to_linear(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+use crate::ray_tracer::cylinder::{turn_fraction, AxisFrame};
+
+// Ring around `axis` through `center`: a tube of `minor_radius` following a circle of
+// `major_radius`. u is the angle around the axis, v the angle around the tube.
+pub struct Torus {
+    frame: AxisFrame,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Torus {
+    pub fn new(center: Point3, axis: Vec3, major_radius: f64, minor_radius: f64, material: Arc<dyn Material>) -> Self {
+        let frame = AxisFrame::new(center, axis);
+        // The ring's extent along each world axis plus the tube all around
+        let ring = |n: f64| major_radius * (1.0 - n * n).max(0.0).sqrt() + minor_radius;
+        let z = frame.z;
+        let reach = Vec3::new(ring(z.x()), ring(z.y()), ring(z.z()));
+        let bbox = Aabb::from_points(center - reach, center + reach);
+        Torus { frame, major_radius, minor_radius, material, bbox }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let (o, d) = self.frame.to_local(r);
+        // Solve in units of distance along the ray, which keeps the quartic well scaled
+        let length = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+        if length == 0.0 {
+            return false;
+        }
+        let d = d.map(|x| x / length);
+
+        // Only the stretch inside the bounding sphere can hit
+        let (big, small) = (self.major_radius, self.minor_radius);
+        let f = o[0] * d[0] + o[1] * d[1] + o[2] * d[2];
+        let oo = o[0] * o[0] + o[1] * o[1] + o[2] * o[2];
+        let bound = big + small;
+        let discriminant = f * f - (oo - bound * bound);
+        if discriminant < 0.0 {
+            return false;
+        }
+        let lo = (-f - discriminant.sqrt()).max(t_range.min * length);
+        let hi = (-f + discriminant.sqrt()).min(t_range.max * length);
+        if lo > hi {
+            return false;
+        }
+
+        // (|p|^2 - R^2 - r^2)^2 = 4 R^2 (r^2 - z^2) along p = o + s d
+        let e = oo - big * big - small * small;
+        let four_r2 = 4.0 * big * big;
+        let coefficients = [
+            1.0,
+            4.0 * f,
+            4.0 * f * f + 2.0 * e + four_r2 * d[2] * d[2],
+            4.0 * f * e + 2.0 * four_r2 * o[2] * d[2],
+            e * e + four_r2 * (o[2] * o[2] - small * small),
+        ];
+        let Some(&s) = real_roots(&coefficients, lo, hi).iter().find(|&&s| s / length >= t_range.min) else {
+            return false;
+        };
+
+        let p = [o[0] + s * d[0], o[1] + s * d[1], o[2] + s * d[2]];
+        let ring_distance = (p[0] * p[0] + p[1] * p[1]).sqrt();
+        // Away from the nearest point on the center circle
+        let (cx, cy) = if ring_distance > 1e-12 {
+            (p[0] / ring_distance * big, p[1] / ring_distance * big)
+        } else {
+            (big, 0.0)
+        };
+        let normal = [(p[0] - cx) / small, (p[1] - cy) / small, p[2] / small];
+
+        rec.t = s / length;
+        rec.p = r.at(rec.t);
+        rec.set_outward_normal(r, Vec3::unit_vector(&self.frame.to_world(normal)));
+        rec.set_tangent(self.frame.to_world([-p[1], p[0], 0.0]));
+        rec.u = turn_fraction(p[0], p[1]);
+        rec.v = turn_fraction(ring_distance - big, p[2]);
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+// Real roots in [lo, hi] of the polynomial with these coefficients, highest degree first,
+// in ascending order. Between consecutive roots of the derivative the polynomial is
+// monotonic, so each stretch holds at most one root, which bisection then finds.
+fn real_roots(coefficients: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+    match degree {
+        0 => return Vec::new(),
+        1 => {
+            let (a, b) = (coefficients[0], coefficients[1]);
+            let root = -b / a;
+            return if a != 0.0 && (lo..=hi).contains(&root) { vec![root] } else { Vec::new() };
+        }
+        _ => {}
+    }
+
+    let evaluate = |x: f64| coefficients.iter().fold(0.0, |acc, c| acc * x + c);
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as f64)
+        .collect();
+
+    let mut bounds = vec![lo];
+    bounds.extend(real_roots(&derivative, lo, hi));
+    bounds.push(hi);
+
+    let mut roots = Vec::new();
+    for pair in bounds.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (mut fa, fb) = (evaluate(a), evaluate(b));
+        if fa == 0.0 {
+            if roots.last() != Some(&a) {
+                roots.push(a);
+            }
+            continue;
+        }
+        if fa * fb > 0.0 {
+            continue;
+        }
+        for _ in 0..64 {
+            let mid = 0.5 * (a + b);
+            let fm = evaluate(mid);
+            if fm == 0.0 || mid == a || mid == b {
+                a = mid;
+                b = mid;
+                break;
+            }
+            if (fm < 0.0) == (fa < 0.0) {
+                a = mid;
+                fa = fm;
+            } else {
+                b = mid;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
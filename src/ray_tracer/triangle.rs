@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use crate::ray_tracer::hittable::Hittable;
+use crate::ray_tracer::ray::Ray;
+use crate::ray_tracer::hit_record::HitRecord;
+use crate::ray_tracer::interval::Interval;
+use crate::ray_tracer::vec3::{Point3, Vec3};
+use crate::ray_tracer::material::Material;
+use crate::ray_tracer::aabb::Aabb;
+
+pub struct Triangle {
+    v0: Point3,
+    e1: Vec3,
+    e2: Vec3,
+    // Geometric normal, follows counter-clockwise winding
+    normal: Vec3,
+    // Direction in which u grows across the face
+    dpdu: Vec3,
+    vertex_normals: Option<[Vec3; 3]>,
+    vertex_uvs: Option<[(f64, f64); 3]>,
+    material: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, material: Arc<dyn Material>) -> Self {
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+        let bbox = Aabb::surrounding(&Aabb::from_points(v0, v1), &Aabb::from_points(v2, v2));
+        Triangle {
+            v0,
+            e1,
+            e2,
+            normal: Vec3::unit_vector(&Vec3::cross(e1, e2)),
+            // Without UVs, u is the barycentric weight of v1
+            dpdu: e1,
+            vertex_normals: None,
+            vertex_uvs: None,
+            material,
+            bbox,
+        }
+    }
+
+    // Interpolate these normals across the face instead of using the flat geometric normal
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.vertex_normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.vertex_uvs = Some(uvs);
+        // Solve e1 = dpdu du1 + dpdv dv1 and e2 = dpdu du2 + dpdv dv2 for dpdu; keep
+        // the edge when the UVs are degenerate
+        let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+        let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+        let det = du1 * dv2 - du2 * dv1;
+        if det.abs() > 1e-12 {
+            self.dpdu = (self.e1 * dv2 - self.e2 * dv1) / det;
+        }
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore ray/triangle intersection
+    #[inline]
+    fn hit(&self, r: &Ray, t_range: Interval, rec: &mut HitRecord) -> bool {
+        let pvec = Vec3::cross(r.direction(), self.e2);
+        let det = Vec3::dot(self.e1, pvec);
+
+        // Ray is parallel to the triangle plane
+        if det.abs() < 1e-12 {
+            return false;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = r.origin() - self.v0;
+        let b1 = Vec3::dot(tvec, pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return false;
+        }
+
+        let qvec = Vec3::cross(tvec, self.e1);
+        let b2 = Vec3::dot(r.direction(), qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return false;
+        }
+
+        let t = Vec3::dot(self.e2, qvec) * inv_det;
+        if t < t_range.min || t > t_range.max {
+            return false;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_outward_normal(r, self.normal);
+        if let Some([n0, n1, n2]) = &self.vertex_normals {
+            let n = *n0 * b0 + *n1 * b1 + *n2 * b2;
+            if !n.near_zero() {
+                // Vertex normals wound the other way round are turned to the geometric side
+                let n = Vec3::unit_vector(&n);
+                rec.set_shading_normal(if Vec3::dot(n, self.normal) < 0.0 { -n } else { n });
+            }
+        }
+        rec.set_tangent(self.dpdu);
+        (rec.u, rec.v) = match &self.vertex_uvs {
+            Some([uv0, uv1, uv2]) => (
+                uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2,
+                uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2,
+            ),
+            None => (b1, b2),
+        };
+        rec.material = Some(Arc::clone(&self.material));
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
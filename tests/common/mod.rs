@@ -0,0 +1,39 @@
+// Fixtures shared by the integration tests; each test file uses its own subset
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::interval::Interval;
+use ray_tracer::ray_tracer::material::{Lambertian, Material};
+use ray_tracer::ray_tracer::ray::Ray;
+use ray_tracer::ray_tracer::scene::{Scene, SceneError};
+use ray_tracer::ray_tracer::vec3::{Color, Point3, Vec3};
+
+pub fn material() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+pub fn v(x: f64, y: f64, z: f64) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+pub fn cast(shape: &dyn Hittable, origin: Point3, direction: Vec3) -> Option<HitRecord> {
+    let mut rec = HitRecord::new();
+    shape.hit(&Ray::new(origin, direction), Interval::new(0.001, f64::INFINITY), &mut rec).then_some(rec)
+}
+
+pub fn assert_close(actual: Vec3, expected: Vec3) {
+    assert!((actual - expected).length() < 1e-9, "expected {:?}, got {:?}", expected, actual);
+}
+
+// The field a scene that fails validation is rejected for
+pub fn invalid_field(source: &str) -> String {
+    match Scene::parse(source, Path::new("")) {
+        Err(SceneError::Invalid { field, .. }) => field,
+        Err(e) => panic!("expected an invalid field, got {}", e),
+        Ok(_) => panic!("scene should have been rejected"),
+    }
+}
@@ -0,0 +1,61 @@
+// Compares each sampler's error against a high sample count reference. Better distributed
+// samples should converge faster than independent random numbers.
+
+use ray_tracer::ray_tracer::camera::Camera;
+use ray_tracer::ray_tracer::integrator::{PathIntegrator, PathSettings};
+use ray_tracer::ray_tracer::renderer::{self, Renderer};
+use ray_tracer::ray_tracer::sampler::SamplerKind;
+use ray_tracer::ray_tracer::scene::Scene;
+use ray_tracer::ray_tracer::vec3::Color;
+
+const WIDTH: u32 = 32;
+
+fn render(sampler: SamplerKind, samples: u32, seed: u64) -> Vec<Color> {
+    let scene = Scene::default_scene();
+    let camera = Camera::new(&scene.camera, renderer::DEFAULT_ASPECT_RATIO);
+    let height = renderer::image_height_for(WIDTH, renderer::DEFAULT_ASPECT_RATIO);
+    let mut renderer = Renderer::new(WIDTH, height, scene, Box::new(PathIntegrator::new(PathSettings::default())));
+    renderer.set_seed(seed);
+    renderer.set_sampler(sampler.with_sample_count(samples));
+    for _ in 0..samples {
+        renderer.render_progressive(&camera);
+    }
+    renderer
+        .get_pixel_data()
+        .iter()
+        .map(|p| p.mean())
+        .collect()
+}
+
+fn rmse(image: &[Color], reference: &[Color]) -> f64 {
+    let sum: f64 = image.iter().zip(reference).map(|(a, b)| (*a - *b).length_squared()).sum();
+    (sum / (3 * image.len()) as f64).sqrt()
+}
+
+#[test]
+fn samplers_converge_faster_than_independent() {
+    let reference = render(SamplerKind::Independent, 2048, 12345);
+    let samplers = [
+        SamplerKind::Independent,
+        SamplerKind::Stratified { strata_per_axis: 4 },
+        SamplerKind::Sobol,
+        SamplerKind::BlueNoise,
+    ];
+
+    let errors: Vec<(f64, f64)> = samplers
+        .iter()
+        .map(|&sampler| (rmse(&render(sampler, 16, 1), &reference), rmse(&render(sampler, 64, 1), &reference)))
+        .collect();
+    for (sampler, (low, high)) in samplers.iter().zip(&errors) {
+        eprintln!("{:?}: rmse {:.5} at 16 spp, {:.5} at 64 spp", sampler, low, high);
+    }
+
+    let (independent_low, independent_high) = errors[0];
+    for (sampler, &(low, high)) in samplers.iter().zip(&errors) {
+        assert!(high < low, "{:?} does not converge: {} -> {}", sampler, low, high);
+    }
+    for (sampler, &(low, high)) in samplers.iter().zip(&errors).skip(1) {
+        assert!(low < independent_low, "{:?} is noisier than independent at 16 spp", sampler);
+        assert!(high < independent_high, "{:?} is noisier than independent at 64 spp", sampler);
+    }
+}
@@ -0,0 +1,79 @@
+// Importance sampling of equirectangular environment maps: the density `sample` reports
+// must be the one `pdf` gives back for the same direction, and it must integrate to one
+// over the sphere, or light sampling and MIS are quietly biased.
+
+use std::f64::consts::PI;
+
+use ray_tracer::ray_tracer::environment::{Environment, EnvironmentMap};
+use ray_tracer::ray_tracer::sampler::{IndependentSampler, Sampler};
+use ray_tracer::ray_tracer::vec3::{Color, Vec3};
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 8;
+
+// A dim sky getting brighter towards the top, a sun, and a black strip
+fn sky(rotation: f64) -> EnvironmentMap {
+    let pixels = (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            if (x, y) == (5, 2) {
+                Color::new(50.0, 45.0, 40.0)
+            } else if x == 11 {
+                Color::new(0.0, 0.0, 0.0)
+            } else {
+                Color::new(0.2, 0.3, 0.5) * (1.0 + (HEIGHT - y) as f64)
+            }
+        })
+        .collect();
+    EnvironmentMap::from_pixels(WIDTH, HEIGHT, pixels, rotation, 1.0).unwrap()
+}
+
+#[test]
+fn sampled_pdfs_match_evaluated_pdfs() {
+    for rotation in [0.0, 30.0] {
+        let map = sky(rotation);
+        let mut sampler = IndependentSampler::new(9);
+        sampler.start_pixel_sample(0, 0, 0);
+        let mut near_sun = 0;
+        for _ in 0..2000 {
+            let Some((direction, pdf)) = map.sample(&mut sampler) else { continue };
+            assert!((direction.length() - 1.0).abs() < 1e-9);
+            assert!(pdf > 0.0 && map.radiance(direction).x() > 0.0, "sampled a black direction");
+            let evaluated = map.pdf(direction);
+            assert!((evaluated - pdf).abs() <= 1e-9 * pdf, "rotation {}: {} vs {}", rotation, pdf, evaluated);
+            near_sun += usize::from(map.radiance(direction).x() > 40.0);
+        }
+        // The sun is one texel of 128 but is picked for about a quarter of the samples
+        assert!(near_sun > 300, "{}", near_sun);
+    }
+}
+
+#[test]
+fn pdf_integrates_to_one_over_the_sphere() {
+    // Midpoint rule in (theta, phi) with the sin(theta) area element
+    let (n_theta, n_phi) = (400, 800);
+    for rotation in [0.0, 30.0] {
+        let map = sky(rotation);
+        let mut total = 0.0;
+        for i in 0..n_theta {
+            let theta = PI * (i as f64 + 0.5) / n_theta as f64;
+            for j in 0..n_phi {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n_phi as f64;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                total += map.pdf(direction) * theta.sin();
+            }
+        }
+        let total = total * (PI / n_theta as f64) * (2.0 * PI / n_phi as f64);
+        assert!((total - 1.0).abs() < 1e-2, "rotation {}: {}", rotation, total);
+    }
+}
+
+#[test]
+fn malformed_pixel_buffers_are_rejected() {
+    let grey = Color::new(0.5, 0.5, 0.5);
+    assert!(EnvironmentMap::from_pixels(0, 4, Vec::new(), 0.0, 1.0).is_err());
+    assert!(EnvironmentMap::from_pixels(4, 0, Vec::new(), 0.0, 1.0).is_err());
+    assert!(EnvironmentMap::from_pixels(4, 2, vec![grey; 7], 0.0, 1.0).is_err());
+    assert!(EnvironmentMap::from_pixels(4, 2, vec![grey; 9], 0.0, 1.0).is_err());
+    assert!(EnvironmentMap::from_pixels(4, 2, vec![grey; 8], 0.0, 1.0).is_ok());
+}
@@ -0,0 +1,64 @@
+// Golden-image regression tests. After an intentional change to the rendered output,
+// regenerate the references with `UPDATE_GOLDEN=1 cargo test --test golden`.
+
+use std::path::PathBuf;
+
+use ray_tracer::ray_tracer::camera::Camera;
+use ray_tracer::ray_tracer::image_writer;
+use ray_tracer::ray_tracer::integrator::{PathIntegrator, PathSettings};
+use ray_tracer::ray_tracer::renderer::{self, Renderer};
+use ray_tracer::ray_tracer::scene::Scene;
+
+const WIDTH: u32 = 64;
+const SAMPLES: u32 = 4;
+
+fn render(seed: u64) -> Vec<u8> {
+    let scene = Scene::default_scene();
+    let camera = Camera::new(&scene.camera, renderer::DEFAULT_ASPECT_RATIO);
+    let height = renderer::image_height_for(WIDTH, renderer::DEFAULT_ASPECT_RATIO);
+    let mut renderer = Renderer::new(WIDTH, height, scene, Box::new(PathIntegrator::new(PathSettings::default())));
+    renderer.set_seed(seed);
+    for _ in 0..SAMPLES {
+        renderer.render_progressive(&camera);
+    }
+    renderer.render_rgba()
+}
+
+fn render_with_threads(threads: usize, seed: u64) -> Vec<u8> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap()
+        .install(|| render(seed))
+}
+
+#[test]
+fn same_seed_gives_identical_pixels_for_any_thread_count() {
+    let single = render_with_threads(1, 42);
+    assert!(single == render_with_threads(3, 42));
+    assert!(single == render_with_threads(8, 42));
+}
+
+#[test]
+fn different_seeds_give_different_noise() {
+    assert!(render(1) != render(2));
+}
+
+#[test]
+fn default_scene_matches_golden_image() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/default_scene.png");
+    let height = renderer::image_height_for(WIDTH, renderer::DEFAULT_ASPECT_RATIO);
+    let pixels = render(0);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        image_writer::write_png(&path, WIDTH, height, &pixels).unwrap();
+        return;
+    }
+
+    let golden = image::open(&path)
+        .unwrap_or_else(|e| panic!("{}: {} (run with UPDATE_GOLDEN=1 to create it)", path.display(), e))
+        .into_rgba8();
+    assert_eq!((golden.width(), golden.height()), (WIDTH, height));
+    let mismatched = golden.as_raw().iter().zip(&pixels).filter(|(a, b)| a != b).count();
+    assert_eq!(mismatched, 0, "{} channel values differ from {}", mismatched, path.display());
+}
@@ -0,0 +1,160 @@
+// What every primitive reports in its HitRecord: both normals, the side that was hit, a
+// tangent frame, UVs, the material and the object id.
+
+mod common;
+
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::bvh::BvhNode;
+use ray_tracer::ray_tracer::cone::Cone;
+use ray_tracer::ray_tracer::cuboid::Cuboid;
+use ray_tracer::ray_tracer::cylinder::Cylinder;
+use ray_tracer::ray_tracer::disk::Disk;
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::hittable_list::HittableList;
+use ray_tracer::ray_tracer::instance::Instance;
+use ray_tracer::ray_tracer::matrix::Matrix4;
+use ray_tracer::ray_tracer::mesh::Mesh;
+use ray_tracer::ray_tracer::plane::Plane;
+use ray_tracer::ray_tracer::quad::Quad;
+use ray_tracer::ray_tracer::sphere::Sphere;
+use ray_tracer::ray_tracer::torus::Torus;
+use ray_tracer::ray_tracer::triangle::Triangle;
+use ray_tracer::ray_tracer::vec3::{Point3, Vec3};
+
+use common::{assert_close, cast, material, v};
+
+// Unit normals, an orthonormal right-handed frame around the shading normal, and
+// front_face agreeing with the geometric normal
+fn assert_consistent(rec: &HitRecord, direction: Vec3) {
+    for n in [rec.normal, rec.geometric_normal, rec.tangent, rec.bitangent] {
+        assert!((n.length() - 1.0).abs() < 1e-9, "not unit length: {:?}", n);
+    }
+    assert!(Vec3::dot(rec.tangent, rec.normal).abs() < 1e-9);
+    assert_close(rec.bitangent, Vec3::cross(rec.normal, rec.tangent));
+    assert_eq!(rec.front_face, Vec3::dot(direction, rec.geometric_normal) < 0.0);
+    assert!(rec.material.is_some());
+}
+
+fn shapes() -> Vec<Arc<dyn Hittable>> {
+    let curved = Triangle::new(v(-1.0, -1.0, 0.0), v(1.0, -1.0, 0.0), v(0.0, 1.0, 0.0), material())
+        .with_normals([v(-0.3, 0.0, 1.0), v(0.3, 0.0, 1.0), v(0.0, 0.3, 1.0)].map(|n| Vec3::unit_vector(&n)))
+        .with_uvs([(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]);
+    vec![
+        Arc::new(Sphere::new(v(0.0, 0.0, 0.0), 1.0, material())),
+        Arc::new(Plane::new(v(0.0, -0.5, 0.0), v(0.2, 1.0, 0.1), material())),
+        Arc::new(Quad::new(v(-1.0, -1.0, 0.0), v(2.0, 0.0, 0.0), v(0.0, 2.0, 0.5), material())),
+        Arc::new(Disk::new(v(0.0, 0.0, 0.0), v(0.0, 0.3, 1.0), 1.5, material())),
+        Arc::new(curved),
+        Arc::new(Cuboid::new(v(-1.0, -0.5, -0.7), v(1.0, 0.5, 0.7), material())),
+        Arc::new(Cylinder::new(v(0.0, -1.0, 0.0), v(0.2, 2.0, 0.0), 0.8, material())),
+        Arc::new(Cone::new(v(0.0, -1.0, 0.0), v(0.0, 2.0, 0.3), 1.0, material())),
+        Arc::new(Torus::new(v(0.0, 0.0, 0.0), v(0.0, 1.0, 0.4), 1.0, 0.4, material())),
+        Arc::new(
+            Instance::new(
+                Arc::new(Cylinder::new(v(0.0, -1.0, 0.0), v(0.0, 2.0, 0.0), 0.5, material())),
+                Matrix4::rotation(v(1.0, 0.0, 1.0), 50.0) * Matrix4::scaling(v(2.0, 1.0, 0.5)),
+            )
+            .unwrap(),
+        ),
+    ]
+}
+
+#[test]
+fn every_primitive_fills_in_a_consistent_record() {
+    for (index, shape) in shapes().iter().enumerate() {
+        let mut hits = 0;
+        for i in 0..15 {
+            for j in 0..15 {
+                let target = v(i as f64 / 7.0 - 1.0, j as f64 / 7.0 - 1.0, 0.1);
+                for origin in [v(0.1, 0.2, 4.0), v(0.3, -0.2, -4.0), v(4.0, 0.1, 0.2), v(0.0, 0.1, 0.05)] {
+                    let direction = target - origin;
+                    if let Some(rec) = cast(shape.as_ref(), origin, direction) {
+                        hits += 1;
+                        assert_consistent(&rec, direction);
+                    }
+                }
+            }
+        }
+        assert!(hits > 50, "shape {} was hit only {} times", index, hits);
+    }
+}
+
+#[test]
+fn tangents_follow_increasing_u() {
+    // Step along the tangent and check that u grows
+    let cases: Vec<(Arc<dyn Hittable>, Point3, Vec3)> = vec![
+        (Arc::new(Sphere::new(v(0.0, 0.0, 0.0), 1.0, material())), v(0.3, 0.2, 5.0), v(0.0, 0.0, -1.0)),
+        (Arc::new(Disk::new(v(0.0, 0.0, 0.0), v(0.0, 0.0, 1.0), 1.0, material())), v(0.3, 0.4, 1.0), v(0.0, 0.0, -1.0)),
+        (Arc::new(Cylinder::new(v(0.0, 0.0, 0.0), v(0.0, 1.0, 0.0), 1.0, material())), v(5.0, 0.5, 0.3), v(-1.0, 0.0, 0.0)),
+        (Arc::new(Cone::new(v(0.0, 0.0, 0.0), v(0.0, 1.0, 0.0), 1.0, material())), v(5.0, 0.5, 0.1), v(-1.0, 0.0, 0.0)),
+        (Arc::new(Torus::new(v(0.0, 0.0, 0.0), v(0.0, 1.0, 0.0), 1.0, 0.3, material())), v(5.0, 0.1, 0.4), v(-1.0, 0.0, 0.0)),
+        (Arc::new(Cuboid::new(v(-1.0, -1.0, -1.0), v(1.0, 1.0, 1.0), material())), v(0.2, 0.3, 5.0), v(0.0, 0.0, -1.0)),
+    ];
+    for (shape, origin, direction) in cases {
+        let rec = cast(shape.as_ref(), origin, direction).unwrap();
+        let nudged = cast(shape.as_ref(), origin + rec.tangent * 1e-4, direction).unwrap();
+        assert!(nudged.u > rec.u, "u went from {} to {}", rec.u, nudged.u);
+        assert!((nudged.v - rec.v).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn triangle_tangent_follows_its_uvs() {
+    // u runs up the y axis here
+    let triangle = Triangle::new(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0), material())
+        .with_uvs([(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)]);
+    let rec = cast(&triangle, v(0.2, 0.2, 1.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert_close(rec.tangent, v(0.0, 1.0, 0.0));
+    assert_close(rec.bitangent, v(-1.0, 0.0, 0.0));
+}
+
+#[test]
+fn smooth_triangles_keep_geometric_and_shading_normals_apart() {
+    let tilted = Vec3::unit_vector(&v(1.0, 0.0, 1.0));
+    let triangle = Triangle::new(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0), material())
+        .with_normals([tilted; 3]);
+
+    let rec = cast(&triangle, v(0.2, 0.2, 1.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert_close(rec.geometric_normal, v(0.0, 0.0, 1.0));
+    assert_close(rec.normal, tilted);
+    assert!(rec.front_face);
+
+    // A grazing ray can arrive in front of the geometry but behind the shading normal;
+    // the side is still decided by the geometry
+    let grazing = v(1.0, 0.0, -0.2);
+    let rec = cast(&triangle, v(0.2, 0.2, 0.0) - grazing, grazing).unwrap();
+    assert!(Vec3::dot(grazing, rec.normal) > 0.0);
+    assert!(rec.front_face);
+
+    // Vertex normals wound against the face are turned to its side
+    let flipped = Triangle::new(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0), material())
+        .with_normals([-tilted; 3]);
+    let rec = cast(&flipped, v(0.2, 0.2, 1.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert_close(rec.normal, tilted);
+}
+
+#[test]
+fn object_ids_index_the_world_list() {
+    let mut list = HittableList::new();
+    for i in 0..5 {
+        list.add(Arc::new(Sphere::new(v(i as f64 * 3.0, 0.0, 0.0), 1.0, material())));
+    }
+    let triangles = vec![
+        Triangle::new(v(0.0, 5.0, -1.0), v(1.0, 5.0, -1.0), v(0.0, 6.0, -1.0), material()),
+        Triangle::new(v(1.0, 5.0, -1.0), v(1.0, 6.0, -1.0), v(0.0, 6.0, -1.0), material()),
+    ];
+    list.add(Arc::new(Mesh::new(triangles)));
+
+    let expected = [(v(0.0, 0.0, 5.0), 0), (v(6.0, 0.0, 5.0), 2), (v(12.0, 0.0, 5.0), 4), (v(0.8, 5.8, 5.0), 5)];
+    for (origin, id) in expected {
+        assert_eq!(cast(&list, origin, v(0.0, 0.0, -1.0)).unwrap().object_id, id);
+    }
+
+    // The same ids through a BVH, with the mesh's own triangles hidden behind its id
+    let bvh = BvhNode::new(list);
+    for (origin, id) in expected {
+        assert_eq!(cast(&bvh, origin, v(0.0, 0.0, -1.0)).unwrap().object_id, id);
+    }
+}
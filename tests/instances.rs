@@ -0,0 +1,180 @@
+// Affine transforms and shared-geometry instances
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::cuboid::Cuboid;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::instance::Instance;
+use ray_tracer::ray_tracer::matrix::Matrix4;
+use ray_tracer::ray_tracer::scene::{Scene, SceneError};
+use ray_tracer::ray_tracer::sphere::Sphere;
+use ray_tracer::ray_tracer::vec3::Vec3;
+
+use common::{assert_close, cast, material, v};
+
+fn assert_matrix_close(a: Matrix4, b: Matrix4) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((a.m[i][j] - b.m[i][j]).abs() < 1e-12, "{:?} != {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn inverse_undoes_the_transform() {
+    let m = Matrix4::translation(v(1.0, -2.0, 3.0))
+        * Matrix4::rotation(v(1.0, 1.0, 0.0), 37.0)
+        * Matrix4::scaling(v(2.0, 0.5, -3.0));
+    assert_matrix_close(m * m.inverse().unwrap(), Matrix4::IDENTITY);
+
+    let p = v(0.3, -1.7, 4.2);
+    assert_close(m.inverse().unwrap().transform_point(m.transform_point(p)), p);
+    assert!(Matrix4::scaling(v(1.0, 0.0, 1.0)).inverse().is_none());
+}
+
+#[test]
+fn axis_rotations_match_the_general_rotation() {
+    for degrees in [0.0, 30.0, 90.0, -135.0] {
+        assert_matrix_close(Matrix4::rotation_x(degrees), Matrix4::rotation(v(1.0, 0.0, 0.0), degrees));
+        assert_matrix_close(Matrix4::rotation_y(degrees), Matrix4::rotation(v(0.0, 1.0, 0.0), degrees));
+        assert_matrix_close(Matrix4::rotation_z(degrees), Matrix4::rotation(v(0.0, 0.0, 1.0), degrees));
+    }
+    // Right-handed: a quarter turn about z takes x to y
+    assert_close(Matrix4::rotation_z(90.0).transform_vector(v(1.0, 0.0, 0.0)), v(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn translated_instance_matches_a_moved_object() {
+    let unit = Arc::new(Sphere::new(v(0.0, 0.0, 0.0), 1.0, material()));
+    let instance = Instance::new(unit, Matrix4::translation(v(2.0, 1.0, -3.0))).unwrap();
+    let moved = Sphere::new(v(2.0, 1.0, -3.0), 1.0, material());
+
+    for direction in [v(0.0, 0.0, -1.0), v(0.3, 0.2, -1.0), v(0.4, -0.1, -1.0)] {
+        let origin = v(0.5, 0.5, 5.0);
+        let a = cast(&instance, origin, direction);
+        let b = cast(&moved, origin, direction);
+        assert_eq!(a.is_some(), b.is_some());
+        if let (Some(a), Some(b)) = (a, b) {
+            assert!((a.t - b.t).abs() < 1e-9);
+            assert_close(a.p, b.p);
+            assert_close(a.normal, b.normal);
+            assert_eq!(a.front_face, b.front_face);
+            assert!((a.u - b.u).abs() < 1e-9 && (a.v - b.v).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn scaled_sphere_has_ellipsoid_normals() {
+    let unit = Arc::new(Sphere::new(v(0.0, 0.0, 0.0), 1.0, material()));
+    let ellipsoid = Instance::new(unit, Matrix4::scaling(v(3.0, 1.0, 1.0))).unwrap();
+
+    let rec = cast(&ellipsoid, v(10.0, 0.0, 0.0), v(-1.0, 0.0, 0.0)).unwrap();
+    assert!((rec.t - 7.0).abs() < 1e-9);
+    assert_close(rec.normal, v(1.0, 0.0, 0.0));
+
+    // On x^2/9 + y^2 = 1 the normal follows the gradient (x/9, y, 0)
+    let origin = v(1.5, 5.0, 0.0);
+    let rec = cast(&ellipsoid, origin, v(0.0, -1.0, 0.0)).unwrap();
+    let expected = Vec3::unit_vector(&v(rec.p.x() / 9.0, rec.p.y(), 0.0));
+    assert!((rec.p.x() * rec.p.x() / 9.0 + rec.p.y() * rec.p.y() - 1.0).abs() < 1e-9);
+    assert_close(rec.normal, expected);
+    assert!(rec.front_face);
+
+    let inside = cast(&ellipsoid, v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+    assert!((inside.t - 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn mirrored_instance_keeps_outward_normals() {
+    let cube = Arc::new(Cuboid::new(v(0.0, 0.0, 0.0), v(1.0, 1.0, 1.0), material()));
+    let mirrored = Instance::new(cube, Matrix4::scaling(v(-1.0, 1.0, 1.0))).unwrap();
+
+    let rec = cast(&mirrored, v(-5.0, 0.5, 0.5), v(1.0, 0.0, 0.0)).unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-9);
+    assert_close(rec.normal, v(-1.0, 0.0, 0.0));
+    assert!(rec.front_face);
+}
+
+#[test]
+fn instances_share_geometry_and_bound_their_hits() {
+    let shared: Arc<dyn Hittable> = Arc::new(Cuboid::new(v(-1.0, -1.0, -1.0), v(1.0, 1.0, 1.0), material()));
+    let instances: Vec<Instance> = (0..100)
+        .map(|i| {
+            let transform = Matrix4::translation(v(i as f64 * 3.0, 0.0, 0.0))
+                * Matrix4::rotation(v(1.0, 2.0, 3.0), i as f64 * 7.0)
+                * Matrix4::scaling(v(1.0, 2.0, 0.5));
+            Instance::new(Arc::clone(&shared), transform).unwrap()
+        })
+        .collect();
+    assert_eq!(Arc::strong_count(&shared), 101);
+
+    for (i, instance) in instances.iter().enumerate() {
+        let bbox = instance.bounding_box();
+        let center = v(i as f64 * 3.0, 0.0, 0.0);
+        for direction in [v(0.0, 0.0, -1.0), v(-1.0, 0.0, 0.0), v(0.0, -1.0, 0.2), v(1.0, 1.0, 1.0)] {
+            let rec = cast(instance, center - direction * 10.0, direction).unwrap();
+            for (axis, p) in [(0, rec.p.x()), (1, rec.p.y()), (2, rec.p.z())] {
+                let range = bbox.axis_interval(axis);
+                assert!(range.min - 1e-9 <= p && p <= range.max + 1e-9);
+            }
+            assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+        }
+    }
+}
+
+const INSTANCED_SCENE: &str = r#"
+[materials.white]
+type = "lambertian"
+albedo = [0.8, 0.8, 0.8]
+
+[geometry.pillar]
+type = "cylinder"
+base = [0.0, 0.0, 0.0]
+axis = [0.0, 1.0, 0.0]
+radius = 0.5
+material = "white"
+
+[[objects]]
+type = "instance"
+geometry = "pillar"
+translation = [2.0, 0.0, 0.0]
+
+[[objects]]
+type = "instance"
+geometry = "pillar"
+translation = [-2.0, 0.0, 0.0]
+rotation = [90.0, 0.0, 0.0]
+scale = [1.0, 3.0, 1.0]
+"#;
+
+#[test]
+fn scene_places_instances_of_named_geometry() {
+    let scene = Scene::parse(INSTANCED_SCENE, Path::new("")).unwrap();
+    assert_eq!(scene.world.len(), 2);
+
+    // The second pillar lies along +z after the quarter turn about x and is 3 long
+    let rec = cast(&scene.world, v(-2.0, 0.0, 10.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!((rec.t - 7.0).abs() < 1e-9);
+    assert_close(rec.normal, v(0.0, 0.0, 1.0));
+    assert!(cast(&scene.world, v(2.0, 5.0, 0.0), v(0.0, -1.0, 0.0)).is_some());
+}
+
+#[test]
+fn scene_rejects_bad_instances() {
+    let unknown = INSTANCED_SCENE.replacen("geometry = \"pillar\"", "geometry = \"column\"", 1);
+    match Scene::parse(&unknown, Path::new("")) {
+        Err(SceneError::Invalid { field, .. }) => assert_eq!(field, "objects[0].geometry"),
+        _ => panic!("unknown geometry should be rejected"),
+    }
+
+    let flat = INSTANCED_SCENE.replace("scale = [1.0, 3.0, 1.0]", "scale = [1.0, 0.0, 1.0]");
+    match Scene::parse(&flat, Path::new("")) {
+        Err(SceneError::Invalid { field, .. }) => assert_eq!(field, "objects[1].scale"),
+        _ => panic!("a zero scale should be rejected"),
+    }
+}
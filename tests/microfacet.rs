@@ -0,0 +1,299 @@
+// The GGX metallic-roughness material: white-furnace energy conservation at every
+// roughness, agreement between sampling and evaluation, and the glTF parameterization.
+
+mod common;
+
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::material::{Lambertian, Material, MetallicRoughness};
+use ray_tracer::ray_tracer::microfacet::{ggx_d, sample_vndf, smith_g1, vndf_reflection_pdf};
+use ray_tracer::ray_tracer::ray::Ray;
+use ray_tracer::ray_tracer::sampler::{IndependentSampler, Sampler};
+use ray_tracer::ray_tracer::scene::Scene;
+use ray_tracer::ray_tracer::texture::ConstantTexture;
+use ray_tracer::ray_tracer::vec3::{Color, Point3, Vec3};
+
+use common::invalid_field;
+
+const ROUGHNESSES: [f64; 7] = [0.0, 0.05, 0.2, 0.4, 0.6, 0.8, 1.0];
+const VIEW_COSINES: [f64; 5] = [0.1, 0.3, 0.6, 0.9, 1.0];
+
+fn white() -> Color {
+    Color::new(1.0, 1.0, 1.0)
+}
+
+// A hit on the z = 0 plane facing +z
+fn record() -> HitRecord {
+    let mut rec = HitRecord::new();
+    rec.normal = Vec3::new(0.0, 0.0, 1.0);
+    rec.geometric_normal = rec.normal;
+    rec.tangent = Vec3::new(1.0, 0.0, 0.0);
+    rec.bitangent = Vec3::new(0.0, 1.0, 0.0);
+    rec
+}
+
+// A ray arriving from elevation cosine `cos_o`
+fn incoming(cos_o: f64) -> Ray {
+    let wo = Vec3::new((1.0 - cos_o * cos_o).sqrt(), 0.0, cos_o);
+    Ray::new(Point3::new(0.0, 0.0, 0.0) + wo, -wo)
+}
+
+// Monte Carlo estimate of the fraction of light reflected towards the viewer, from the
+// material's own importance sampling
+fn albedo(material: &dyn Material, cos_o: f64, samples: u32) -> Color {
+    let (rec, r_in) = (record(), incoming(cos_o));
+    let mut sampler = IndependentSampler::new(17);
+    sampler.start_pixel_sample(0, 0, 0);
+    let mut sum = Color::new(0.0, 0.0, 0.0);
+    for _ in 0..samples {
+        if let Some(sample) = material.sample(&r_in, &rec, &mut sampler) {
+            sum += sample.weight();
+        }
+    }
+    sum / samples as f64
+}
+
+#[test]
+fn white_furnace_conserves_energy_at_every_roughness() {
+    for metallic in [0.0, 0.5, 1.0] {
+        for roughness in ROUGHNESSES {
+            let material = MetallicRoughness::new(white(), metallic, roughness);
+            for cos_o in VIEW_COSINES {
+                let a = albedo(&material, cos_o, 20_000);
+                for channel in [a.x(), a.y(), a.z()] {
+                    assert!(
+                        (channel - 1.0).abs() < 0.02,
+                        "metallic {} roughness {} cos {}: albedo {:?}",
+                        metallic, roughness, cos_o, a
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn colored_and_dark_surfaces_never_gain_energy() {
+    let base = Color::new(0.9, 0.5, 0.1);
+    for metallic in [0.0, 0.3, 1.0] {
+        for roughness in ROUGHNESSES {
+            let material = MetallicRoughness::new(base, metallic, roughness);
+            for cos_o in VIEW_COSINES {
+                let a = albedo(&material, cos_o, 5_000);
+                assert!(a.x() <= 1.0 + 0.02 && a.y() <= 1.0 + 0.02 && a.z() <= 1.0 + 0.02, "{:?}", a);
+                // Brighter base channels reflect at least as much
+                assert!(a.x() + 0.02 >= a.y() && a.y() + 0.02 >= a.z(), "{:?}", a);
+            }
+        }
+    }
+
+    // Black metal only keeps Schlick's faint grazing reflection; a black dielectric its 4% coat
+    let black = Color::new(0.0, 0.0, 0.0);
+    let metal = albedo(&MetallicRoughness::new(black, 1.0, 0.5), 0.8, 2_000);
+    assert!(metal.x() < 0.02, "{:?}", metal);
+    let coat = albedo(&MetallicRoughness::new(black, 0.0, 0.0), 1.0, 2_000);
+    assert!((coat.x() - 0.04).abs() < 0.005, "{:?}", coat);
+}
+
+#[test]
+fn albedo_is_what_sample_weights_average_to() {
+    let base = Color::new(0.9, 0.5, 0.1);
+    for (metallic, roughness) in [(0.0, 0.05), (0.0, 0.6), (0.5, 0.3), (1.0, 0.0), (1.0, 0.8)] {
+        let material = MetallicRoughness::new(base, metallic, roughness);
+        for cos_o in VIEW_COSINES {
+            let expected = albedo(&material, cos_o, 20_000);
+            let exact = material.albedo(&incoming(cos_o), &record());
+            assert!(
+                (exact - expected).length() < 0.02,
+                "metallic {} roughness {} cos {}: {:?} vs {:?}",
+                metallic, roughness, cos_o, exact, expected
+            );
+            assert!(exact.x() <= 1.0 && exact.y() <= 1.0 && exact.z() <= 1.0, "{:?}", exact);
+        }
+    }
+}
+
+#[test]
+fn smooth_metal_reflects_its_base_color_head_on() {
+    let base = Color::new(0.95, 0.64, 0.54);
+    let a = albedo(&MetallicRoughness::new(base, 1.0, 0.0), 1.0, 2_000);
+    assert!((a - base).length() < 0.01, "{:?}", a);
+}
+
+#[test]
+fn sampled_values_and_pdfs_match_evaluation() {
+    let material = MetallicRoughness::new(Color::new(0.7, 0.6, 0.5), 0.4, 0.5);
+    let rec = record();
+    let mut sampler = IndependentSampler::new(3);
+    sampler.start_pixel_sample(0, 0, 0);
+    for cos_o in VIEW_COSINES {
+        let r_in = incoming(cos_o);
+        for _ in 0..200 {
+            let Some(sample) = material.sample(&r_in, &rec, &mut sampler) else { continue };
+            assert!(!sample.is_specular);
+            let value = material.scattering_value(&r_in, &rec, sample.wi);
+            let pdf = material.scattering_pdf(&r_in, &rec, sample.wi);
+            assert!((value - sample.value).length() < 1e-9 * (1.0 + value.length()));
+            assert!((pdf - sample.pdf).abs() < 1e-9 * (1.0 + pdf));
+        }
+    }
+}
+
+#[test]
+fn pdf_integrates_to_at_most_one() {
+    // Midpoint rule over the hemisphere in (cos theta, phi). Rough reflections that would
+    // go below the surface are dropped, so the total falls short of one.
+    let (rec, n) = (record(), 400);
+    for roughness in [0.3, 0.6, 1.0] {
+        let material = MetallicRoughness::new(Color::new(0.5, 0.5, 0.5), 0.5, roughness);
+        for cos_o in [0.2, 0.7, 1.0] {
+            let r_in = incoming(cos_o);
+            let mut total = 0.0;
+            for i in 0..n {
+                let z = (i as f64 + 0.5) / n as f64;
+                let r = (1.0 - z * z).sqrt();
+                for j in 0..n {
+                    let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+                    total += material.scattering_pdf(&r_in, &rec, Vec3::new(r * phi.cos(), r * phi.sin(), z));
+                }
+            }
+            let total = total * 2.0 * PI / (n * n) as f64;
+            assert!(total <= 1.0 + 1e-2 && total > 0.6, "roughness {} cos {}: {}", roughness, cos_o, total);
+        }
+    }
+}
+
+#[test]
+fn visible_normals_follow_their_density() {
+    // The normals sample_vndf picks face the viewer and are distributed as
+    // G1(wo) max(0, wo.h) D(h) / wo.z, which integrates to one
+    let alpha = 0.5;
+    let wo = Vec3::unit_vector(&Vec3::new(0.6, 0.2, 0.5));
+    let mut sampler = IndependentSampler::new(5);
+    sampler.start_pixel_sample(0, 0, 0);
+    for _ in 0..1000 {
+        let h = sample_vndf(wo, alpha, sampler.next_2d());
+        assert!(h.z() > 0.0 && Vec3::dot(wo, h) >= -1e-12);
+        assert!(((h.length()) - 1.0).abs() < 1e-9);
+    }
+
+    let n = 400;
+    let mut total = 0.0;
+    for i in 0..n {
+        let z = (i as f64 + 0.5) / n as f64;
+        let r = (1.0 - z * z).sqrt();
+        for j in 0..n {
+            let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+            let h = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+            total += smith_g1(wo, alpha) * Vec3::dot(wo, h).max(0.0) * ggx_d(h, alpha) / wo.z();
+        }
+    }
+    assert!((total * 2.0 * PI / (n * n) as f64 - 1.0).abs() < 1e-2);
+
+    // The reflected direction's pdf is the normal's over the reflection Jacobian 4 wo.h
+    let h = Vec3::unit_vector(&Vec3::new(0.1, -0.2, 1.0));
+    let expected = smith_g1(wo, alpha) * Vec3::dot(wo, h) * ggx_d(h, alpha) / wo.z() / (4.0 * Vec3::dot(wo, h));
+    assert!((vndf_reflection_pdf(wo, h, alpha) - expected).abs() < 1e-12);
+}
+
+#[test]
+fn packed_texture_follows_gltf_channels() {
+    // Roughness in green, metallic in blue, scaled by the factors; red is ignored
+    let packed = Arc::new(ConstantTexture::new(Color::new(0.9, 0.6, 1.0)));
+    let base = Color::new(0.8, 0.3, 0.2);
+    let textured = MetallicRoughness::textured(Arc::new(ConstantTexture::new(base)), 1.0, 0.5, Some(packed));
+    let plain = MetallicRoughness::new(base, 1.0, 0.3);
+
+    let rec = record();
+    let r_in = incoming(0.7);
+    for wi in [Vec3::new(-0.5, 0.1, 0.8), Vec3::new(0.3, 0.3, 0.9), Vec3::new(-0.7, 0.0, 0.2)] {
+        let wi = Vec3::unit_vector(&wi);
+        let a = textured.scattering_value(&r_in, &rec, wi);
+        let b = plain.scattering_value(&r_in, &rec, wi);
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+}
+
+// Sampled direction for a fixed sampler state
+fn first_sample(material: &dyn Material, r_in: &Ray, rec: &HitRecord) -> Vec3 {
+    let mut sampler = IndependentSampler::new(11);
+    sampler.start_pixel_sample(0, 0, 0);
+    material.sample(r_in, rec, &mut sampler).unwrap().wi
+}
+
+#[test]
+fn samples_follow_the_hit_tangent_frame() {
+    let materials: [Box<dyn Material>; 2] =
+        [Box::new(Lambertian::new(white())), Box::new(MetallicRoughness::new(white(), 0.5, 0.6))];
+    // The same hit with its tangent turned a quarter around the normal
+    let mut turned = record();
+    turned.tangent = Vec3::new(0.0, 1.0, 0.0);
+    turned.bitangent = Vec3::new(-1.0, 0.0, 0.0);
+    // A view direction that is also a quarter turn apart
+    let wo = Vec3::unit_vector(&Vec3::new(0.6, 0.0, 0.8));
+    let turned_wo = Vec3::new(0.0, wo.x(), wo.z());
+
+    // Seen from below, the normal and bitangent flip, so the hit reads as a front face
+    // with the flipped frame
+    let mut back = record();
+    back.front_face = false;
+    let mut flipped = record();
+    flipped.normal = -back.normal;
+    flipped.bitangent = -back.bitangent;
+    let below = Vec3::new(wo.x(), wo.y(), -wo.z());
+
+    for material in &materials {
+        let wi = first_sample(material.as_ref(), &Ray::new(Point3::new(0.0, 0.0, 0.0) + wo, -wo), &record());
+        let turned_wi = first_sample(material.as_ref(), &Ray::new(Point3::new(0.0, 0.0, 0.0) + turned_wo, -turned_wo), &turned);
+        assert!((turned_wi - Vec3::new(-wi.y(), wi.x(), wi.z())).length() < 1e-9, "{:?} vs {:?}", wi, turned_wi);
+
+        let r_below = Ray::new(Point3::new(0.0, 0.0, 0.0) + below, -below);
+        let a = first_sample(material.as_ref(), &r_below, &back);
+        let b = first_sample(material.as_ref(), &r_below, &flipped);
+        assert!(a.z() < 0.0 && (a - b).length() < 1e-12, "{:?} vs {:?}", a, b);
+    }
+}
+
+const PBR_SCENE: &str = r#"
+[textures.orm]
+type = "constant"
+color = [1.0, 0.5, 0.0]
+
+[materials.plastic]
+type = "pbr"
+base_color = [0.8, 0.1, 0.1]
+metallic = 0.0
+roughness = 0.4
+
+[materials.brushed]
+type = "pbr"
+metallic_roughness = "orm"
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "plastic"
+
+[[objects]]
+type = "sphere"
+center = [3.0, 0.0, 0.0]
+radius = 1.0
+material = "brushed"
+"#;
+
+#[test]
+fn scene_builds_pbr_materials() {
+    let scene = Scene::parse(PBR_SCENE, Path::new("")).unwrap();
+    assert_eq!(scene.world.len(), 2);
+
+    assert_eq!(invalid_field(&PBR_SCENE.replace("roughness = 0.4", "roughness = 1.5")), "materials.plastic.roughness");
+    assert_eq!(invalid_field(&PBR_SCENE.replace("metallic = 0.0", "metallic = -0.1")), "materials.plastic.metallic");
+    assert_eq!(
+        invalid_field(&PBR_SCENE.replace("metallic_roughness = \"orm\"", "metallic_roughness = \"rough\"")),
+        "materials.brushed.metallic_roughness"
+    );
+}
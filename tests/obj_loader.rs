@@ -0,0 +1,204 @@
+// Wavefront OBJ import: fan triangulation, absolute and relative indices, materials from
+// MTL libraries, and the errors reported for faces that point past the data.
+
+mod common;
+
+use std::f64::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::material::{Lambertian, Material};
+use ray_tracer::ray_tracer::obj_loader::{load_obj, ObjError};
+use ray_tracer::ray_tracer::ray::Ray;
+use ray_tracer::ray_tracer::scene::{Scene, SceneError};
+use ray_tracer::ray_tracer::vec3::Color;
+
+use common::{assert_close, cast, v};
+
+// Writes `files` into a fresh directory of their own and returns its path
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ray_tracer_obj_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+fn fallback() -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+}
+
+// Material of the face straight below (x, y) on the z = 0 plane
+fn material_at(mesh: &dyn Hittable, x: f64, y: f64) -> Option<(HitRecord, Arc<dyn Material>)> {
+    let rec = cast(mesh, v(x, y, 5.0), v(0.0, 0.0, -1.0))?;
+    let material = Arc::clone(rec.material.as_ref().unwrap());
+    Some((rec, material))
+}
+
+// Lambertian albedo seen head on
+fn albedo(rec: &HitRecord, material: &dyn Material) -> Color {
+    let r_in = Ray::new(v(0.0, 0.0, 5.0), v(0.0, 0.0, -1.0));
+    material.scattering_value(&r_in, rec, v(0.0, 0.0, 1.0)) * PI
+}
+
+const MTL: &str = "\
+newmtl red
+Kd 1 0 0
+
+newmtl blue   # comments are ignored
+Kd 0 0 1
+
+newmtl lamp
+Ke 4 4 4
+";
+
+const OBJ: &str = "\
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 0 0
+v 3 0 0
+v 3 1 0
+v 2.5 1.5 0
+v 2 1 0
+usemtl red
+f 1 2 3 4
+usemtl blue
+f 5 6 7 8 9
+v 4 0 0
+v 5 0 0
+v 5 1 0
+usemtl missing
+f -3 -2 -1
+v 6 0 0
+v 7 0 0
+v 7 1 0
+usemtl lamp
+f -3 -2 -1
+";
+
+#[test]
+fn polygons_are_fan_triangulated_with_their_materials() {
+    let dir = write_files("materials", &[("scene.obj", OBJ), ("scene.mtl", MTL)]);
+    let fallback = fallback();
+    let mesh = load_obj(&dir.join("scene.obj"), Arc::clone(&fallback));
+    fs::remove_dir_all(&dir).unwrap();
+    let mesh = mesh.unwrap();
+
+    // A quad, a pentagon and two triangles
+    assert_eq!(mesh.triangle_count(), 2 + 3 + 1 + 1);
+
+    let (rec, red) = material_at(&mesh, 0.5, 0.5).unwrap();
+    assert_close(albedo(&rec, red.as_ref()), Color::new(1.0, 0.0, 0.0));
+    // In the pentagon's pointed top, past its square part
+    let (rec, blue) = material_at(&mesh, 2.5, 1.2).unwrap();
+    assert_close(albedo(&rec, blue.as_ref()), Color::new(0.0, 0.0, 1.0));
+
+    // An unknown material falls back to the one given
+    let (_, unknown) = material_at(&mesh, 4.8, 0.5).unwrap();
+    assert!(Arc::ptr_eq(&unknown, &fallback));
+
+    let (rec, lamp) = material_at(&mesh, 6.8, 0.5).unwrap();
+    assert_close(lamp.emitted(&rec), Color::new(4.0, 4.0, 4.0));
+}
+
+#[test]
+fn relative_indices_count_back_from_the_latest_vertex() {
+    let dir = write_files("relative", &[("scene.obj", OBJ), ("scene.mtl", MTL)]);
+    let mesh = load_obj(&dir.join("scene.obj"), fallback());
+    fs::remove_dir_all(&dir).unwrap();
+    let mesh = mesh.unwrap();
+
+    // `f -3 -2 -1` after vertices 10 to 12 is the triangle (4, 0), (5, 0), (5, 1)
+    assert!(material_at(&mesh, 4.8, 0.5).is_some());
+    assert!(material_at(&mesh, 4.2, 0.9).is_none());
+    assert!(material_at(&mesh, 3.5, 0.5).is_none());
+}
+
+#[test]
+fn bad_indices_are_reported_at_their_line() {
+    let header = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n";
+    let cases = [
+        ("f 1 2 4", "vertex index 4 out of range"),
+        ("f 0 1 2", "vertex index 0 out of range"),
+        ("f -4 1 2", "vertex index -4 out of range"),
+        ("f 1/2 2/1 3/1", "texture coordinate index 2 out of range"),
+        ("f 1//1 2//1 3//1", "normal index 1 out of range"),
+        ("f 1 2", "face needs at least 3 vertices"),
+    ];
+    for (face, expected) in cases {
+        let dir = write_files("indices", &[("bad.obj", &format!("{}# the bad face\n{}\n", header, face))]);
+        let result = load_obj(&dir.join("bad.obj"), fallback());
+        fs::remove_dir_all(&dir).unwrap();
+
+        match result {
+            Err(ObjError::Parse { line, message, .. }) => {
+                assert_eq!(line, 6, "{}", face);
+                assert!(message.starts_with(expected), "{}: {}", face, message);
+            }
+            Err(e) => panic!("{}: expected a parse error, got {}", face, e),
+            Ok(_) => panic!("{}: should have been rejected", face),
+        }
+    }
+}
+
+#[test]
+fn missing_and_empty_files_are_errors() {
+    let dir = write_files("empty", &[("empty.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\n")]);
+    let empty = load_obj(&dir.join("empty.obj"), fallback());
+    let missing = load_obj(&dir.join("missing.obj"), fallback());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(empty, Err(ObjError::Empty { .. })));
+    assert!(matches!(missing, Err(ObjError::Io { .. })));
+}
+
+#[test]
+fn a_missing_material_library_leaves_the_fallback() {
+    let dir = write_files("no_mtl", &[("scene.obj", OBJ)]);
+    let fallback = fallback();
+    let mesh = load_obj(&dir.join("scene.obj"), Arc::clone(&fallback));
+    fs::remove_dir_all(&dir).unwrap();
+    let mesh = mesh.unwrap();
+
+    for (x, y) in [(0.5, 0.5), (2.5, 0.5), (6.8, 0.5)] {
+        let (_, material) = material_at(&mesh, x, y).unwrap();
+        assert!(Arc::ptr_eq(&material, &fallback));
+    }
+}
+
+#[test]
+fn missing_texture_maps_fail_the_scene() {
+    let mtl = format!("{}\nnewmtl wood\nmap_Kd missing.png\n", MTL);
+    let scene = "[materials.grey]\ntype = \"lambertian\"\nalbedo = [0.5, 0.5, 0.5]\n\n\
+                 [[objects]]\ntype = \"mesh\"\npath = \"scene.obj\"\nmaterial = \"grey\"\n";
+    let dir = write_files("map_kd", &[("scene.obj", OBJ), ("scene.mtl", &mtl)]);
+    let mesh = load_obj(&dir.join("scene.obj"), fallback());
+    let loaded = Scene::parse(scene, &dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    match mesh {
+        Err(ObjError::Texture { path, line, message }) => {
+            assert_eq!(path.file_name(), Some(Path::new("scene.mtl").as_os_str()));
+            assert_eq!(line, 11);
+            assert!(message.contains("missing.png"), "{}", message);
+        }
+        Err(e) => panic!("expected a texture error, got {}", e),
+        Ok(_) => panic!("a missing map_Kd should fail the mesh"),
+    }
+    match loaded {
+        Err(SceneError::Invalid { field, message, .. }) => {
+            assert_eq!(field, "objects[0].path");
+            assert!(message.contains("scene.mtl:11"), "{}", message);
+        }
+        Err(e) => panic!("expected an invalid field, got {}", e),
+        Ok(_) => panic!("a missing map_Kd should fail the scene"),
+    }
+}
@@ -0,0 +1,59 @@
+// Running per-pixel statistics: Welford's single-sample update and Chan's merge of two
+// partial accumulators, which temporal reuse and adaptive sampling both rely on.
+
+use ray_tracer::ray_tracer::pixel_data::PixelData;
+use ray_tracer::ray_tracer::vec3::Color;
+
+// Deterministic samples with a large common offset, where naive sums of squares cancel out
+fn samples(count: usize) -> Vec<Color> {
+    (0..count)
+        .map(|i| {
+            let x = (i as f64 * 0.618_033_988_75).fract();
+            Color::new(1e4 + x, 0.5 * x * x, if i % 3 == 0 { 2.0 } else { 0.0 })
+        })
+        .collect()
+}
+
+fn accumulate(samples: &[Color]) -> PixelData {
+    let mut pixel = PixelData::new();
+    for &sample in samples {
+        pixel.add_sample(sample);
+    }
+    pixel
+}
+
+fn assert_close(actual: Color, expected: Color, tolerance: f64) {
+    assert!((actual - expected).length() < tolerance, "expected {:?}, got {:?}", expected, actual);
+}
+
+#[test]
+fn welford_matches_two_pass_statistics() {
+    let samples = samples(101);
+    let n = samples.len() as f64;
+    let mean = samples.iter().fold(Color::new(0.0, 0.0, 0.0), |sum, &s| sum + s) / n;
+    let variance = samples.iter().fold(Color::new(0.0, 0.0, 0.0), |sum, &s| sum + (s - mean) * (s - mean)) / (n - 1.0);
+
+    let pixel = accumulate(&samples);
+    assert_eq!(pixel.sample_count, 101);
+    assert_close(pixel.mean(), mean, 1e-9);
+    assert_close(pixel.variance(), variance, 1e-9);
+
+    // A single sample has no spread yet
+    assert_close(accumulate(&samples[..1]).variance(), Color::new(0.0, 0.0, 0.0), 1e-12);
+}
+
+#[test]
+fn merging_partial_accumulators_matches_one_pass() {
+    let samples = samples(100);
+    let whole = accumulate(&samples);
+
+    for split in [0, 1, 37, 99, 100] {
+        let mut merged = accumulate(&samples[..split]);
+        let rest = accumulate(&samples[split..]);
+        merged.merge(rest.mean(), rest.m2, rest.sample_count);
+
+        assert_eq!(merged.sample_count, whole.sample_count, "split at {}", split);
+        assert_close(merged.mean(), whole.mean(), 1e-9);
+        assert_close(merged.variance(), whole.variance(), 1e-9);
+    }
+}
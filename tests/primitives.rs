@@ -0,0 +1,264 @@
+// Hit and miss tests for the analytic primitives, including rays that only just touch or
+// just miss a surface.
+
+mod common;
+
+use ray_tracer::ray_tracer::cone::Cone;
+use ray_tracer::ray_tracer::cuboid::Cuboid;
+use ray_tracer::ray_tracer::cylinder::Cylinder;
+use ray_tracer::ray_tracer::disk::Disk;
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::hittable::Hittable;
+use ray_tracer::ray_tracer::plane::Plane;
+use ray_tracer::ray_tracer::quad::Quad;
+use ray_tracer::ray_tracer::sphere::Sphere;
+use ray_tracer::ray_tracer::torus::Torus;
+use ray_tracer::ray_tracer::vec3::Vec3;
+
+use common::{assert_close, cast, material, v};
+
+const EPS: f64 = 1e-6;
+
+fn assert_unit_uv(rec: &HitRecord) {
+    assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v), "uv ({}, {})", rec.u, rec.v);
+}
+
+// Every hit point has to lie inside the bounding box or the BVH would cull it
+fn assert_hits_inside_bounds(shape: &dyn Hittable) {
+    let bbox = shape.bounding_box();
+    let mut hits = 0;
+    for i in 0..40 {
+        for j in 0..40 {
+            let target = v(i as f64 / 10.0 - 2.0, j as f64 / 10.0 - 2.0, 0.0);
+            for origin in [v(0.3, 0.2, 5.0), v(5.0, -0.4, 0.1), v(-0.2, 5.0, 0.3)] {
+                if let Some(rec) = cast(shape, origin, target - origin) {
+                    hits += 1;
+                    for (axis, p) in [(0, rec.p.x()), (1, rec.p.y()), (2, rec.p.z())] {
+                        let range = bbox.axis_interval(axis);
+                        assert!(range.min - EPS <= p && p <= range.max + EPS, "{:?} outside {:?}", rec.p, bbox);
+                    }
+                }
+            }
+        }
+    }
+    assert!(hits > 0);
+}
+
+#[test]
+fn sphere_front_face_and_grazing() {
+    let sphere = Sphere::new(v(0.0, 0.0, 0.0), 1.0, material());
+
+    let rec = cast(&sphere, v(0.0, 0.0, 5.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(0.0, 0.0, 1.0));
+    assert_unit_uv(&rec);
+
+    let inside = cast(&sphere, v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+    assert_close(inside.normal, v(1.0, 0.0, 0.0));
+
+    assert!(cast(&sphere, v(-5.0, 1.0 + EPS, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    let graze = cast(&sphere, v(-5.0, 1.0 - EPS, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(graze.front_face && graze.normal.y() > 0.99);
+}
+
+#[test]
+fn plane_faces_and_grazing() {
+    let plane = Plane::new(v(0.0, -0.5, 0.0), v(0.0, 2.0, 0.0), material());
+
+    let rec = cast(&plane, v(1.0, 1.5, 2.0), v(0.0, -1.0, 0.0)).unwrap();
+    assert!((rec.t - 2.0).abs() < 1e-9);
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(0.0, 1.0, 0.0));
+
+    let below = cast(&plane, v(1.0, -3.0, 2.0), v(0.0, 1.0, 0.0)).unwrap();
+    assert!(!below.front_face);
+    assert_close(below.normal, v(0.0, 1.0, 0.0));
+
+    // Parallel rays miss, nearly parallel ones hit far away
+    assert!(cast(&plane, v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    let graze = cast(&plane, v(0.0, 0.0, 0.0), v(1.0, -1e-4, 0.0)).unwrap();
+    assert!((graze.p.y() + 0.5).abs() < 1e-6);
+    assert!((graze.p.x() - 5000.0).abs() < 1e-3);
+
+    // UVs move in world units across the plane
+    let a = cast(&plane, v(0.0, 1.0, 0.0), v(0.0, -1.0, 0.0)).unwrap();
+    let b = cast(&plane, v(3.0, 1.0, 4.0), v(0.0, -1.0, 0.0)).unwrap();
+    assert!(((b.u - a.u).powi(2) + (b.v - a.v).powi(2) - 25.0).abs() < 1e-9);
+}
+
+#[test]
+fn quad_edges() {
+    let quad = Quad::new(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0), v(0.0, 1.0, 0.0), material());
+    let rec = cast(&quad, v(0.25, 0.75, 1.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(0.0, 0.0, 1.0));
+    assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.75).abs() < 1e-9);
+
+    let back = cast(&quad, v(0.5, 0.5, -1.0), v(0.0, 0.0, 1.0)).unwrap();
+    assert!(!back.front_face);
+
+    assert!(cast(&quad, v(1.0 + EPS, 0.5, 1.0), v(0.0, 0.0, -1.0)).is_none());
+    assert!(cast(&quad, v(1.0 - EPS, 0.5, 1.0), v(0.0, 0.0, -1.0)).is_some());
+    assert!(cast(&quad, v(-1.0, 0.5, 0.0), v(1.0, 0.0, 0.0)).is_none());
+}
+
+#[test]
+fn disk_rim_and_uv() {
+    let disk = Disk::new(v(0.0, 0.0, 0.0), v(0.0, 0.0, 1.0), 1.0, material());
+
+    let rec = cast(&disk, v(0.0, 0.5, 2.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(0.0, 0.0, 1.0));
+    assert!((rec.v - 0.5).abs() < 1e-9);
+    assert_unit_uv(&rec);
+
+    assert!(!cast(&disk, v(0.0, 0.5, -2.0), v(0.0, 0.0, 1.0)).unwrap().front_face);
+    assert!(cast(&disk, v(1.0 + EPS, 0.0, 2.0), v(0.0, 0.0, -1.0)).is_none());
+    let rim = cast(&disk, v(1.0 - EPS, 0.0, 2.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!(rim.v > 0.999);
+
+    // Grazing: a ray in the disk's own plane sees nothing
+    assert!(cast(&disk, v(-5.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    assert!(cast(&disk, v(-5.0, 0.0, 5e-4), v(1.0, 0.0, -1e-4)).is_some());
+
+    assert_hits_inside_bounds(&Disk::new(v(0.2, 0.1, 0.0), v(1.0, 2.0, 3.0), 1.0, material()));
+}
+
+#[test]
+fn box_faces_inside_and_edges() {
+    let cube = Cuboid::new(v(-1.0, -1.0, -1.0), v(1.0, 1.0, 1.0), material());
+
+    let rec = cast(&cube, v(0.0, 0.0, 5.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-9);
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(0.0, 0.0, 1.0));
+    assert!((rec.u - 0.5).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+
+    let inside = cast(&cube, v(0.0, 0.0, 0.0), v(0.0, -1.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+    assert_close(inside.normal, v(0.0, -1.0, 0.0));
+    assert!((inside.t - 1.0).abs() < 1e-9);
+
+    // Skimming over the top face
+    assert!(cast(&cube, v(-5.0, 1.0 + EPS, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    let skim = cast(&cube, v(-5.0, 1.0 - EPS, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert_close(skim.normal, v(-1.0, 0.0, 0.0));
+    assert_unit_uv(&skim);
+
+    // Past a corner along a diagonal
+    assert!(cast(&cube, v(-5.0, 0.0, -3.0 + 1e-3), v(1.0, 0.0, 1.0)).is_none());
+    assert!(cast(&cube, v(-5.0, 0.0, -3.0 - 1e-3), v(1.0, 0.0, 1.0)).is_some());
+}
+
+#[test]
+fn rotated_box_edge_faces_the_ray() {
+    // Turned 45 degrees about y, so an edge points straight at +z
+    let s = std::f64::consts::FRAC_1_SQRT_2;
+    let axes = [v(s, 0.0, -s), v(0.0, 1.0, 0.0), v(s, 0.0, s)];
+    let cube = Cuboid::oriented(v(0.0, 0.0, 0.0), [1.0, 1.0, 1.0], axes, material());
+
+    let rec = cast(&cube, v(EPS, 0.0, 5.0), v(0.0, 0.0, -1.0)).unwrap();
+    assert!((rec.t - (5.0 - 2.0f64.sqrt())).abs() < 1e-4);
+    assert!(rec.front_face);
+    assert!((rec.normal.length() - 1.0).abs() < 1e-9);
+    assert!(cast(&cube, v(2.0f64.sqrt() + EPS, 0.0, 5.0), v(0.0, 0.0, -1.0)).is_none());
+
+    assert_hits_inside_bounds(&cube);
+}
+
+#[test]
+fn cylinder_side_caps_and_grazing() {
+    let cylinder = Cylinder::new(v(0.0, 0.0, 0.0), v(0.0, 2.0, 0.0), 1.0, material());
+
+    let side = cast(&cylinder, v(5.0, 1.0, 0.0), v(-1.0, 0.0, 0.0)).unwrap();
+    assert!((side.t - 4.0).abs() < 1e-9);
+    assert!(side.front_face);
+    assert_close(side.normal, v(1.0, 0.0, 0.0));
+    assert!((side.v - 0.5).abs() < 1e-9);
+
+    let top = cast(&cylinder, v(0.2, 5.0, 0.0), v(0.0, -1.0, 0.0)).unwrap();
+    assert_close(top.normal, v(0.0, 1.0, 0.0));
+    assert!((top.v - 0.2).abs() < 1e-9);
+    assert_unit_uv(&top);
+
+    let inside = cast(&cylinder, v(0.0, 1.0, 0.0), v(0.0, -1.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+    assert_close(inside.normal, v(0.0, -1.0, 0.0));
+
+    // Tangent to the side, and skimming over the top cap
+    assert!(cast(&cylinder, v(1.0 + EPS, 1.0, 5.0), v(0.0, 0.0, -1.0)).is_none());
+    assert!(cast(&cylinder, v(1.0 - EPS, 1.0, 5.0), v(0.0, 0.0, -1.0)).is_some());
+    assert!(cast(&cylinder, v(-5.0, 2.0 + EPS, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    let skim = cast(&cylinder, v(-5.0, 2.0 - EPS, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert_close(skim.normal, v(-1.0, 0.0, 0.0));
+
+    assert_hits_inside_bounds(&Cylinder::new(v(0.0, -1.0, 0.0), v(1.0, 2.0, -0.5), 0.5, material()));
+}
+
+#[test]
+fn cone_side_base_and_apex() {
+    // Height 2, base radius 1: the side slopes at 1 across for 2 up
+    let cone = Cone::new(v(0.0, 0.0, 0.0), v(0.0, 2.0, 0.0), 1.0, material());
+
+    let side = cast(&cone, v(5.0, 1.0, 0.0), v(-1.0, 0.0, 0.0)).unwrap();
+    assert!((side.t - 4.5).abs() < 1e-9);
+    assert!(side.front_face);
+    assert_close(side.normal, Vec3::unit_vector(&v(2.0, 1.0, 0.0)));
+    assert!((side.v - 0.5).abs() < 1e-9);
+
+    let base = cast(&cone, v(0.3, -5.0, 0.0), v(0.0, 1.0, 0.0)).unwrap();
+    assert_close(base.normal, v(0.0, -1.0, 0.0));
+    assert!((base.v - 0.3).abs() < 1e-9);
+
+    let inside = cast(&cone, v(0.0, 0.5, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+
+    // Just above the apex is the mirrored half of the double cone, which is not part of it
+    assert!(cast(&cone, v(-5.0, 2.0 + EPS, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    assert!(cast(&cone, v(-5.0, 2.0 - 1e-3, 0.0), v(1.0, 0.0, 0.0)).is_some());
+    // Tangent to the slant at half height
+    assert!(cast(&cone, v(0.5 + EPS, 1.0, 5.0), v(0.0, 0.0, -1.0)).is_none());
+    assert!(cast(&cone, v(0.5 - EPS, 1.0, 5.0), v(0.0, 0.0, -1.0)).is_some());
+    // Parallel to the far slant, so the side equation has a single root
+    let along = cast(&cone, v(1.0, 3.0, 0.0), v(-1.0, -2.0, 0.0)).unwrap();
+    assert!((along.t - 0.75).abs() < 1e-9);
+    assert!(along.front_face);
+    assert_close(along.p, v(0.25, 1.5, 0.0));
+
+    assert_hits_inside_bounds(&Cone::new(v(0.0, -1.0, 0.0), v(-1.0, 2.0, 0.5), 0.8, material()));
+}
+
+#[test]
+fn torus_tube_hole_and_grazing() {
+    let torus = Torus::new(v(0.0, 0.0, 0.0), v(0.0, 1.0, 0.0), 1.0, 0.25, material());
+
+    let rec = cast(&torus, v(-3.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!((rec.t - 1.75).abs() < 1e-9);
+    assert!(rec.front_face);
+    assert_close(rec.normal, v(-1.0, 0.0, 0.0));
+    assert_unit_uv(&rec);
+
+    // Unnormalized directions give the same point
+    let scaled = cast(&torus, v(-3.0, 0.0, 0.0), v(4.0, 0.0, 0.0)).unwrap();
+    assert!((scaled.t - 1.75 / 4.0).abs() < 1e-9);
+
+    // From the hole onto the inner wall, then from inside the tube
+    let wall = cast(&torus, v(-0.5, 0.0, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!((wall.t - 1.25).abs() < 1e-9);
+    assert_close(wall.normal, v(-1.0, 0.0, 0.0));
+    let inside = cast(&torus, v(1.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(!inside.front_face);
+    assert!((inside.t - 0.25).abs() < 1e-9);
+
+    // Straight down the hole
+    assert!(cast(&torus, v(0.0, 5.0, 0.0), v(0.0, -1.0, 0.0)).is_none());
+
+    // Skimming over the top of the ring
+    assert!(cast(&torus, v(-3.0, 0.25 + EPS, 0.0), v(1.0, 0.0, 0.0)).is_none());
+    let graze = cast(&torus, v(-3.0, 0.25 - 1e-4, 0.0), v(1.0, 0.0, 0.0)).unwrap();
+    assert!(graze.normal.y() > 0.9);
+    assert!((graze.p.x() + 1.0).abs() < 0.01);
+
+    assert_hits_inside_bounds(&Torus::new(v(0.1, 0.0, -0.2), v(0.3, 1.0, 0.6), 1.0, 0.3, material()));
+}
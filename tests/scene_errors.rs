@@ -0,0 +1,82 @@
+// Malformed scene files are reported at the line of the value at fault, with its field
+
+use std::path::Path;
+
+use ray_tracer::ray_tracer::scene::{Scene, SceneError};
+
+// The sphere's radius sits on line 11, below its [[objects]] header on line 8
+const SCENE: &str = r#"[camera]
+fov = 40.0
+
+[materials.white]
+type = "lambertian"
+albedo = [0.8, 0.8, 0.8]
+
+[[objects]]
+type = "sphere"
+center = [0.0, 0.0, 0.0]
+radius = 1.0
+material = "white"
+"#;
+
+#[test]
+fn wrong_type_points_at_the_value() {
+    let source = SCENE.replace("radius = 1.0", "radius = \"big\"");
+    match Scene::parse(&source, Path::new("")) {
+        Err(SceneError::Parse { line, field, message, .. }) => {
+            assert_eq!(line, 11);
+            assert_eq!(field.as_deref(), Some("objects[0].radius"));
+            assert!(message.contains("invalid type"), "{}", message);
+        }
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+
+    // The entry's type is checked like any other value
+    let source = SCENE.replace("type = \"sphere\"", "type = \"spere\"");
+    match Scene::parse(&source, Path::new("")) {
+        Err(SceneError::Parse { line, field, .. }) => {
+            assert_eq!(line, 9);
+            assert_eq!(field.as_deref(), Some("objects[0].type"));
+        }
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn unknown_field_points_at_its_line() {
+    let source = SCENE.replace("albedo = [0.8, 0.8, 0.8]", "albedo = [0.8, 0.8, 0.8]\ncolour = [1.0, 0.0, 0.0]");
+    match Scene::parse(&source, Path::new("")) {
+        Err(SceneError::Parse { line, field, message, .. }) => {
+            assert_eq!(line, 7);
+            assert_eq!(field.as_deref(), Some("materials.white.colour"));
+            assert!(message.contains("unknown field"), "{}", message);
+        }
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn failed_validation_points_at_the_value() {
+    let source = SCENE.replace("radius = 1.0", "radius = -1.0");
+    let error = Scene::parse(&source, Path::new("")).err().expect("a negative radius should be rejected");
+    assert_eq!(error.to_string(), "line 11: objects[0].radius: must be positive");
+
+    let source = SCENE.replace("fov = 40.0", "fov = 200.0");
+    match Scene::parse(&source, Path::new("")) {
+        Err(SceneError::Invalid { line, field, .. }) => {
+            assert_eq!(line, 2);
+            assert_eq!(field, "camera.fov");
+        }
+        other => panic!("expected an invalid field, got {:?}", other.err()),
+    }
+
+    // A required key that was left out is reported at the entry
+    let source = SCENE.replace("radius = 1.0\n", "");
+    match Scene::parse(&source, Path::new("")) {
+        Err(SceneError::Parse { line, field, .. }) => {
+            assert_eq!(line, 8);
+            assert_eq!(field.as_deref(), Some("objects[0].radius"));
+        }
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+}
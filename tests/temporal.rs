@@ -0,0 +1,92 @@
+// Reprojection of the previous frame's samples: projecting a direction back onto the image
+// plane, finding the same pixel again for a still camera, and rejecting disocclusions.
+
+use ray_tracer::ray_tracer::camera::Camera;
+use ray_tracer::ray_tracer::pixel_data::PixelData;
+use ray_tracer::ray_tracer::scene::CameraSettings;
+use ray_tracer::ray_tracer::temporal::History;
+use ray_tracer::ray_tracer::vec3::{Color, Vec3};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+const DEPTH: f32 = 3.0;
+
+fn camera() -> Camera {
+    Camera::new(&CameraSettings::default(), WIDTH as f64 / HEIGHT as f64)
+}
+
+// Image coordinates of a pixel's center, with t measured from the bottom
+fn center(x: u32, y: u32) -> (f64, f64) {
+    ((x as f64 + 0.5) / WIDTH as f64, ((HEIGHT - 1 - y) as f64 + 0.5) / HEIGHT as f64)
+}
+
+// A wall facing the camera, every pixel with its own color
+fn wall() -> Vec<PixelData> {
+    (0..WIDTH * HEIGHT)
+        .map(|i| {
+            let mut pixel = PixelData::new();
+            for sample in [0.2, 0.4] {
+                pixel.add_sample(Color::new(i as f64, sample, 0.0));
+            }
+            pixel.depth = DEPTH;
+            pixel.normal = Vec3::new(0.0, 0.0, 1.0);
+            pixel
+        })
+        .collect()
+}
+
+#[test]
+fn projection_inverts_primary_rays() {
+    let mut camera = camera();
+    // Turned 30 degrees right and 20 up, and moved off the origin
+    camera.process_mouse_movement(300.0, 200.0);
+    camera.position += Vec3::new(1.0, -2.0, 0.5);
+    let viewport = camera.viewport();
+
+    for (s, t) in [(0.5, 0.5), (0.0, 0.0), (1.0, 1.0), (0.1, 0.9), (-0.3, 1.4)] {
+        let (ps, pt) = viewport.project(viewport.get_ray(s, t).direction()).unwrap();
+        assert!((ps - s).abs() < 1e-9 && (pt - t).abs() < 1e-9, "({}, {}) came back as ({}, {})", s, t, ps, pt);
+    }
+
+    // Directions behind the camera never cross the image plane
+    assert!(viewport.project(-viewport.get_ray(0.5, 0.5).direction()).is_none());
+}
+
+#[test]
+fn still_camera_finds_the_same_pixel() {
+    let camera = camera();
+    let viewport = camera.viewport();
+    let history = History::new(&camera, WIDTH, HEIGHT, wall());
+    let previous = wall();
+
+    for (x, y) in [(0, 0), (3, 2), (WIDTH - 1, HEIGHT - 1), (5, 1)] {
+        let (s, t) = center(x, y);
+        let index = (y * WIDTH + x) as usize;
+        let sample = history.reproject(&viewport.get_ray(s, t), &previous[index]).unwrap();
+        assert!((sample.mean - previous[index].mean()).length() < 1e-9, "pixel ({}, {}): {:?}", x, y, sample.mean);
+        assert_eq!(sample.sample_count, 2);
+    }
+}
+
+#[test]
+fn depth_jump_is_rejected_as_disocclusion() {
+    let camera = camera();
+    let viewport = camera.viewport();
+    let history = History::new(&camera, WIDTH, HEIGHT, wall());
+
+    // Something twice as far away shows through where the wall used to be
+    let (s, t) = center(3, 2);
+    let mut uncovered = PixelData::new();
+    uncovered.add_sample(Color::new(1.0, 1.0, 1.0));
+    uncovered.depth = 2.0 * DEPTH;
+    uncovered.normal = Vec3::new(0.0, 0.0, 1.0);
+    assert!(history.reproject(&viewport.get_ray(s, t), &uncovered).is_none());
+
+    // So does the sky
+    uncovered.depth = f32::INFINITY;
+    assert!(history.reproject(&viewport.get_ray(s, t), &uncovered).is_none());
+
+    // A small change in depth is still the same surface
+    uncovered.depth = DEPTH * 1.01;
+    assert!(history.reproject(&viewport.get_ray(s, t), &uncovered).is_some());
+}
@@ -0,0 +1,236 @@
+// Texture lookups, filtering and wrapping, procedural noise, and textures in scene files
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use ray_tracer::ray_tracer::hit_record::HitRecord;
+use ray_tracer::ray_tracer::material::{DiffuseLight, Lambertian, Material};
+use ray_tracer::ray_tracer::ray::Ray;
+use ray_tracer::ray_tracer::scene::Scene;
+use ray_tracer::ray_tracer::texture::{
+    CheckerSpace, CheckerTexture, ConstantTexture, ImageTexture, NoisePattern, NoiseTexture, Texture, WrapMode,
+};
+use ray_tracer::ray_tracer::tonemap::{linear_to_srgb, srgb_to_linear};
+use ray_tracer::ray_tracer::vec3::{Color, Point3, Vec3};
+
+use common::invalid_field;
+
+fn c(r: f64, g: f64, b: f64) -> Color {
+    Color::new(r, g, b)
+}
+
+fn origin() -> Point3 {
+    Point3::new(0.0, 0.0, 0.0)
+}
+
+fn assert_close(actual: Color, expected: Color) {
+    assert!((actual - expected).length() < 1e-9, "expected {:?}, got {:?}", expected, actual);
+}
+
+// Black and white columns over a red and a green texel in the bottom row
+fn two_by_two(wrap: WrapMode) -> ImageTexture {
+    let pixels = vec![c(0.0, 0.0, 0.0), c(1.0, 1.0, 1.0), c(1.0, 0.0, 0.0), c(0.0, 1.0, 0.0)];
+    ImageTexture::from_pixels(2, 2, pixels, wrap)
+}
+
+#[test]
+fn image_texels_are_hit_at_their_centers_with_v_up() {
+    let image = two_by_two(WrapMode::Clamp);
+    assert_close(image.value(0.25, 0.75, origin()), c(0.0, 0.0, 0.0));
+    assert_close(image.value(0.75, 0.75, origin()), c(1.0, 1.0, 1.0));
+    assert_close(image.value(0.25, 0.25, origin()), c(1.0, 0.0, 0.0));
+    assert_close(image.value(0.75, 0.25, origin()), c(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn image_lookups_are_bilinear() {
+    let image = two_by_two(WrapMode::Clamp);
+    // Halfway between the two texels of the top row, then the middle of all four
+    assert_close(image.value(0.5, 0.75, origin()), c(0.5, 0.5, 0.5));
+    assert_close(image.value(0.5, 0.5, origin()), c(0.5, 0.5, 0.25));
+    // A quarter of the way from the black texel to the white one
+    assert_close(image.value(0.375, 0.75, origin()), c(0.25, 0.25, 0.25));
+}
+
+#[test]
+fn wrap_modes_differ_outside_the_unit_square() {
+    let (repeat, mirror, clamp) = (two_by_two(WrapMode::Repeat), two_by_two(WrapMode::Mirror), two_by_two(WrapMode::Clamp));
+
+    // One texel past the right edge of the top row
+    let (u, v) = (1.25, 0.75);
+    assert_close(repeat.value(u, v, origin()), c(0.0, 0.0, 0.0));
+    assert_close(mirror.value(u, v, origin()), c(1.0, 1.0, 1.0));
+    assert_close(clamp.value(u, v, origin()), c(1.0, 1.0, 1.0));
+
+    // Far away, clamping holds the edge and repeating comes back around
+    assert_close(clamp.value(-7.75, 0.75, origin()), c(0.0, 0.0, 0.0));
+    assert_close(repeat.value(3.75, 0.75, origin()), c(1.0, 1.0, 1.0));
+    assert_close(repeat.value(-0.75, 0.75, origin()), c(0.0, 0.0, 0.0));
+
+    // At the seam repeat blends the two edges while clamp keeps the edge color
+    assert_close(repeat.value(1.0, 0.75, origin()), c(0.5, 0.5, 0.5));
+    assert_close(clamp.value(1.0, 0.75, origin()), c(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn png_textures_are_linearized() {
+    assert!((srgb_to_linear(linear_to_srgb(0.2)) - 0.2).abs() < 1e-12);
+
+    let path = std::env::temp_dir().join(format!("ray_tracer_texture_{}.png", std::process::id()));
+    image::RgbImage::from_fn(2, 1, |x, _| if x == 0 { image::Rgb([188, 0, 255]) } else { image::Rgb([0, 0, 0]) })
+        .save(&path)
+        .unwrap();
+    let texture = ImageTexture::load(&path, WrapMode::Clamp);
+    std::fs::remove_file(&path).unwrap();
+
+    let texture = texture.unwrap();
+    assert_eq!(texture.size(), (2, 1));
+    let texel = texture.value(0.25, 0.5, origin());
+    // sRGB 188 is about half the linear intensity
+    assert!((texel.x() - 0.5).abs() < 0.01, "{:?}", texel);
+    assert_close(c(0.0, texel.y(), texel.z()), c(0.0, 0.0, 1.0));
+
+    assert!(ImageTexture::load(Path::new("does/not/exist.png"), WrapMode::Repeat).is_err());
+}
+
+#[test]
+fn checkers_alternate_in_uv_and_world_space() {
+    let black: Arc<dyn Texture> = Arc::new(ConstantTexture::new(c(0.0, 0.0, 0.0)));
+    let white: Arc<dyn Texture> = Arc::new(ConstantTexture::new(c(1.0, 1.0, 1.0)));
+
+    let uv = CheckerTexture::new(Arc::clone(&black), Arc::clone(&white), 4.0, CheckerSpace::Uv);
+    assert_close(uv.value(0.1, 0.1, origin()), c(0.0, 0.0, 0.0));
+    assert_close(uv.value(0.3, 0.1, origin()), c(1.0, 1.0, 1.0));
+    assert_close(uv.value(0.3, 0.3, origin()), c(0.0, 0.0, 0.0));
+
+    let world = CheckerTexture::new(black, white, 1.0, CheckerSpace::World);
+    assert_close(world.value(0.0, 0.0, Point3::new(0.5, 0.5, 0.5)), c(0.0, 0.0, 0.0));
+    assert_close(world.value(0.0, 0.0, Point3::new(-0.5, 0.5, 0.5)), c(1.0, 1.0, 1.0));
+    assert_close(world.value(0.0, 0.0, Point3::new(-0.5, -0.5, 0.5)), c(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn noise_stays_between_its_colors_and_is_deterministic() {
+    let (low, high) = (c(0.1, 0.2, 0.3), c(0.9, 0.8, 0.7));
+    for pattern in [NoisePattern::Perlin, NoisePattern::Fbm, NoisePattern::Turbulence, NoisePattern::Marble] {
+        let a = NoiseTexture::new(pattern, 3.0, 5, low, high, 7);
+        let b = NoiseTexture::new(pattern, 3.0, 5, low, high, 7);
+        let other_seed = NoiseTexture::new(pattern, 3.0, 5, low, high, 8);
+
+        let mut differs = false;
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        for i in 0..500 {
+            let p = Point3::new(i as f64 * 0.137, (i * 7 % 13) as f64 * 0.29, -(i as f64) * 0.071);
+            let value = a.value(0.0, 0.0, p);
+            assert_close(value, b.value(0.0, 0.0, p));
+            differs |= (value - other_seed.value(0.0, 0.0, p)).length() > 1e-6;
+
+            // Every channel sits at the same point between low and high
+            let t = (value.x() - low.x()) / (high.x() - low.x());
+            assert!((-1e-9..=1.0 + 1e-9).contains(&t), "{:?} out of range: {}", pattern, t);
+            assert_close(value, low * (1.0 - t) + high * t);
+            min = min.min(t);
+            max = max.max(t);
+        }
+        assert!(differs, "{:?} ignores its seed", pattern);
+        assert!(max - min > 0.3, "{:?} barely varies: {}..{}", pattern, min, max);
+    }
+}
+
+#[test]
+fn materials_evaluate_textures_at_the_hit() {
+    let texture: Arc<dyn Texture> = Arc::new(CheckerTexture::new(
+        Arc::new(ConstantTexture::new(c(0.2, 0.2, 0.2))),
+        Arc::new(ConstantTexture::new(c(1.0, 0.5, 0.0))),
+        2.0,
+        CheckerSpace::Uv,
+    ));
+    let mut rec = HitRecord::new();
+    rec.normal = Vec3::new(0.0, 0.0, 1.0);
+    rec.geometric_normal = rec.normal;
+    let r_in = Ray::new(Point3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let wi = Vec3::new(0.0, 0.0, 1.0);
+
+    let light = DiffuseLight::textured(Arc::clone(&texture), 4.0);
+    let lambertian = Lambertian::textured(texture);
+    for ((u, v), expected) in [((0.25, 0.25), c(0.2, 0.2, 0.2)), ((0.75, 0.25), c(1.0, 0.5, 0.0))] {
+        rec.u = u;
+        rec.v = v;
+        assert_close(light.emitted(&rec), expected * 4.0);
+        assert_close(lambertian.scattering_value(&r_in, &rec, wi), expected / std::f64::consts::PI);
+    }
+}
+
+const TEXTURED_SCENE: &str = r#"
+[textures.tiles]
+type = "checker"
+even = [0.1, 0.1, 0.1]
+odd = [0.9, 0.9, 0.9]
+scale = 8.0
+
+[textures.marble]
+type = "noise"
+pattern = "marble"
+scale = 2.0
+
+[materials.floor]
+type = "lambertian"
+albedo = "tiles"
+
+[materials.stone]
+type = "metal"
+albedo = [0.9, 0.9, 0.9]
+fuzz = "marble"
+
+[materials.glow]
+type = "emissive"
+color = "marble"
+intensity = 2.0
+
+[[objects]]
+type = "plane"
+point = [0.0, 0.0, 0.0]
+normal = [0.0, 1.0, 0.0]
+material = "floor"
+
+[[objects]]
+type = "sphere"
+center = [0.0, 1.0, 0.0]
+radius = 1.0
+material = "stone"
+
+[[objects]]
+type = "sphere"
+center = [3.0, 1.0, 0.0]
+radius = 1.0
+material = "glow"
+"#;
+
+#[test]
+fn scene_materials_refer_to_textures_by_name() {
+    let scene = Scene::parse(TEXTURED_SCENE, Path::new("")).unwrap();
+    assert_eq!(scene.world.len(), 3);
+}
+
+#[test]
+fn scene_reports_texture_errors_at_the_field() {
+    let unknown = TEXTURED_SCENE.replace("albedo = \"tiles\"", "albedo = \"bricks\"");
+    assert_eq!(invalid_field(&unknown), "materials.floor.albedo");
+
+    let unknown_fuzz = TEXTURED_SCENE.replace("fuzz = \"marble\"", "fuzz = \"granite\"");
+    assert_eq!(invalid_field(&unknown_fuzz), "materials.stone.fuzz");
+
+    let missing_image = format!("{}\n[textures.wood]\ntype = \"image\"\npath = \"missing.png\"\n", TEXTURED_SCENE);
+    assert_eq!(invalid_field(&missing_image), "textures.wood.path");
+
+    let bad_wrap = format!("{}\n[textures.wood]\ntype = \"image\"\npath = \"wood.png\"\nwrap = \"tile\"\n", TEXTURED_SCENE);
+    assert_eq!(invalid_field(&bad_wrap), "textures.wood.wrap");
+
+    let bad_pattern = TEXTURED_SCENE.replace("pattern = \"marble\"", "pattern = \"granite\"");
+    assert_eq!(invalid_field(&bad_pattern), "textures.marble.pattern");
+
+    let bad_scale = TEXTURED_SCENE.replace("scale = 8.0", "scale = 0.0");
+    assert_eq!(invalid_field(&bad_scale), "textures.tiles.scale");
+}